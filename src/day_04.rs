@@ -1,5 +1,10 @@
 use crate::utils;
-use std::{collections::HashSet, str::FromStr, vec};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    str::FromStr,
+    vec,
+};
 
 const INPUT: &str = include_str!("input/2023_04.txt");
 
@@ -8,28 +13,111 @@ pub fn run() -> String {
     utils::both(part_one(&cards), part_two(&cards))
 }
 
+/// Show how the copies of each card were won.
+pub fn trace() -> String {
+    Simulation::run(&parse_input(INPUT)).to_string()
+}
+
 fn part_one(cards: &[Card]) -> usize {
     cards.iter().map(Card::points).sum()
 }
 
 fn part_two(cards: &[Card]) -> usize {
-    let mut counts = vec![1_usize; cards.len()];
-    cards
-        .iter()
-        .map(Card::number_of_winners)
-        .enumerate()
-        .for_each(|(current_idx, winners)| {
-            for new_idx in current_idx + 1..=current_idx + winners {
+    Simulation::run(cards).total()
+}
+
+/// The cards held once every copy has been scratched, and which card won
+/// which copies.
+#[derive(Debug)]
+struct Simulation {
+    ids: Vec<usize>,
+    winners: Vec<usize>,
+    /// Copies held of each card, including the original.
+    copies: Vec<usize>,
+    /// For each card, the later cards it won and how many copies of each.
+    won: Vec<Vec<(usize, usize)>>,
+}
+
+impl Simulation {
+    fn run(cards: &[Card]) -> Self {
+        let winners: Vec<usize> = cards.iter().map(Card::number_of_winners).collect();
+        let mut copies = vec![1_usize; cards.len()];
+        let mut won = vec![Vec::new(); cards.len()];
+        for (current_idx, &count) in winners.iter().enumerate() {
+            // Winning past the last card gets you nothing.
+            let last_idx = (current_idx + count).min(cards.len() - 1);
+            for new_idx in current_idx + 1..=last_idx {
                 // We get 1 of each new card for each of the current card.
-                counts[new_idx] += counts[current_idx];
+                copies[new_idx] += copies[current_idx];
+                won[current_idx].push((new_idx, copies[current_idx]));
             }
-        });
-    counts.iter().sum()
+        }
+        Self {
+            ids: cards.iter().map(|c| c.id).collect(),
+            winners,
+            copies,
+            won,
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.copies.iter().sum()
+    }
+
+    /// Id and number of copies of the card held the most times, the
+    /// earliest if there's a tie.
+    fn most_copied(&self) -> Option<(usize, usize)> {
+        let (idx, &copies) = self
+            .copies
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, copies)| copies)?;
+        Some((self.ids[idx], copies))
+    }
+
+    /// How many cards have each number of winning numbers.
+    fn winner_distribution(&self) -> BTreeMap<usize, usize> {
+        let mut distribution = BTreeMap::new();
+        for &count in &self.winners {
+            *distribution.entry(count).or_default() += 1;
+        }
+        distribution
+    }
+}
+
+impl Display for Simulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Card\tWinners\tCopies\tWon")?;
+        for (idx, id) in self.ids.iter().enumerate() {
+            let won: Vec<String> = self.won[idx]
+                .iter()
+                .map(|&(new_idx, copies)| format!("{}x{}", copies, self.ids[new_idx]))
+                .collect();
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}",
+                id,
+                self.winners[idx],
+                self.copies[idx],
+                won.join(" ")
+            )?;
+        }
+        writeln!(f, "Total cards:\t{}", self.total())?;
+        if let Some((id, copies)) = self.most_copied() {
+            writeln!(f, "Most copies:\tcard {id} ({copies})")?;
+        }
+        let distribution: Vec<String> = self
+            .winner_distribution()
+            .iter()
+            .map(|(winners, cards)| format!("{winners}: {cards}"))
+            .collect();
+        write!(f, "Cards by winners:\t{}", distribution.join(", "))
+    }
 }
 
 #[derive(Debug)]
 struct Card {
-    #[allow(dead_code)]
     id: usize,
     winners: HashSet<u8>,
     candidates: HashSet<u8>,
@@ -91,7 +179,7 @@ fn parse_input(input: &str) -> Vec<Card> {
 mod test {
     use crate::day_04::{part_one, part_two};
 
-    use super::{parse_input, Card};
+    use super::{parse_input, Card, Simulation};
 
     const TEST_INPUT: &str = "\
         Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53\n\
@@ -125,4 +213,49 @@ mod test {
         let answer = part_two(&cards);
         assert_eq!(answer, 30);
     }
+
+    #[test]
+    fn day4_simulation() {
+        let simulation = Simulation::run(&parse_input(TEST_INPUT));
+        assert_eq!(simulation.copies, [1, 2, 4, 8, 14, 1]);
+        assert_eq!(simulation.winners, [4, 2, 2, 1, 0, 0]);
+        assert_eq!(simulation.won[0], [(1, 1), (2, 1), (3, 1), (4, 1)]);
+        assert_eq!(simulation.won[1], [(2, 2), (3, 2)]);
+        assert_eq!(simulation.won[3], [(4, 8)]);
+        assert!(simulation.won[5].is_empty());
+        assert_eq!(simulation.total(), 30);
+        assert_eq!(simulation.most_copied(), Some((5, 14)));
+        assert_eq!(
+            simulation.winner_distribution(),
+            [(0, 2), (1, 1), (2, 2), (4, 1)].into()
+        );
+    }
+
+    #[test]
+    fn day4_simulation_report() {
+        let simulation = Simulation::run(&parse_input(TEST_INPUT));
+        let expected = "\
+Card\tWinners\tCopies\tWon
+1\t4\t1\t1x2 1x3 1x4 1x5
+2\t2\t2\t2x3 2x4
+3\t2\t4\t4x4 4x5
+4\t1\t8\t8x5
+5\t0\t14\t
+6\t0\t1\t
+Total cards:\t30
+Most copies:\tcard 5 (14)
+Cards by winners:\t0: 2, 1: 1, 2: 2, 4: 1";
+        assert_eq!(simulation.to_string(), expected);
+    }
+
+    #[test]
+    fn day4_simulation_ties_and_overrun() {
+        // The last card can't win copies of cards that don't exist.
+        let cards = parse_input("Card 1: 1 2 | 3 4\nCard 2: 5 6 | 5 6");
+        let simulation = Simulation::run(&cards);
+        assert_eq!(simulation.copies, [1, 1]);
+        assert!(simulation.won[1].is_empty());
+        assert_eq!(simulation.most_copied(), Some((1, 1)));
+        assert_eq!(Simulation::run(&[]).most_copied(), None);
+    }
 }
@@ -1,30 +1,105 @@
-use crate::utils;
-use std::ops::Range;
+use crate::utils::{self, IntervalSet};
+use itertools::Itertools;
+use std::{
+    fmt::{Display, Write},
+    ops::Range,
+};
 
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{digit1, multispace0, multispace1},
-    combinator::value,
-    multi::many1,
-    sequence::{preceded, terminated},
+    character::complete::{line_ending, multispace0, multispace1, space0, space1, u64},
+    combinator::{eof, value},
+    multi::{many1, separated_list1},
+    sequence::{preceded, tuple},
     IResult,
 };
 
 const INPUT: &str = include_str!("input/2023_05.txt");
 
 pub fn run() -> String {
-    let (seeds, maps) = parse_input_single_seeds(INPUT).unwrap().1;
-    let p1 = part_one(&seeds, &maps);
-
-    let (seeds, maps) = parse_input_seed_ranges(INPUT).unwrap().1;
-    let p2 = chain_ranges(&seeds, &maps).0;
+    let raw = parse_input(INPUT).unwrap().1;
+    let (seeds, ranges) = (raw.single_seeds(), raw.seed_ranges().unwrap());
+    let almanac = match raw.almanac() {
+        Ok(almanac) => almanac,
+        Err(e) => return e,
+    };
+    let seed_to_location = almanac.query(Element::Seed, Element::Location).unwrap();
+    let p1 = part_one(&seeds, &seed_to_location);
+    let p2 = part_two(&ranges, &seed_to_location).0;
 
     utils::both(p1, p2)
 }
 
-fn part_one(seeds: &[Seed], maps: &[Map]) -> u64 {
-    seeds.iter().map(|s| chain(s, maps)).min().unwrap().0
+/// Both parts again, but translating one map at a time and coalescing
+/// the seed ranges between maps, rather than through the composed almanac.
+pub fn staged() -> String {
+    let raw = parse_input(INPUT).unwrap().1;
+    let (seeds, ranges) = (raw.single_seeds(), raw.seed_ranges().unwrap());
+    let almanac = match raw.almanac() {
+        Ok(almanac) => almanac,
+        Err(e) => return e,
+    };
+    let p1 = seeds.iter().map(|s| chain(s, &almanac.maps)).min().unwrap();
+    match chain_ranges(&ranges, &almanac.maps) {
+        Ok(p2) => utils::both(p1.0, p2.0),
+        Err(e) => e.to_owned(),
+    }
+}
+
+/// Describe each map in the almanac and where it leaves values alone,
+/// or what's wrong with it.
+pub fn check() -> String {
+    describe_maps(&parse_input(INPUT).unwrap().1)
+}
+
+fn describe_maps(raw: &RawAlmanac) -> String {
+    let mut lines: Vec<String> = raw
+        .maps
+        .iter()
+        .map(|map| map.validate().map_or_else(|e| e, |map| map.to_string()))
+        .collect();
+    if let Ok(maps) = raw.maps() {
+        if let Err(e) = Almanac::new(maps) {
+            lines.push(e.to_owned());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Explain where the lowest location comes from in each part.
+pub fn explain() -> String {
+    let raw = parse_input(INPUT).unwrap().1;
+    let (seeds, ranges) = (raw.single_seeds(), raw.seed_ranges().unwrap());
+    let almanac = match raw.almanac() {
+        Ok(almanac) => almanac,
+        Err(e) => return e,
+    };
+    let (p1, p2) = match (almanac.lowest(&seeds), almanac.lowest(&ranges)) {
+        (Ok(p1), Ok(p2)) => (p1, p2),
+        (Err(e), _) | (_, Err(e)) => return e.to_owned(),
+    };
+    format!("Part one:\n{p1}\n\nPart two:\n{p2}")
+}
+
+fn part_one(seeds: &[Seed], almanac: &PiecewiseMap) -> u64 {
+    seeds
+        .iter()
+        .filter_map(Seed::as_single)
+        .map(|&s| almanac.translate(s))
+        .min()
+        .unwrap()
+}
+
+/// Lowest location for the seed ranges, translated through the whole
+/// almanac at once.
+fn part_two(seeds: &[Seed], almanac: &PiecewiseMap) -> Location {
+    let locations: IntervalSet<u64> = seeds
+        .iter()
+        .filter_map(Seed::as_range)
+        .flat_map(|r| almanac.translate_range(r.clone()))
+        .collect();
+    Location(locations.min().unwrap())
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -34,7 +109,6 @@ enum Seed {
 }
 
 impl Seed {
-    #[allow(dead_code)]
     fn as_single(&self) -> Option<&u64> {
         if let Self::Single(v) = self {
             Some(v)
@@ -52,51 +126,482 @@ impl Seed {
     }
 }
 
+/// A map whose lines are sorted by source and don't overlap, with
+/// neighbouring lines that move by the same amount merged together.
 #[derive(Debug)]
 struct Map {
-    #[allow(dead_code)]
     kind: MapKind,
     lines: Vec<MapLine>,
 }
 
+/// Two lines of a map whose sources share some values, which would make
+/// the result depend on which line is checked first.
+#[derive(Debug, PartialEq, Eq)]
+struct Overlap {
+    /// Positions of the lines as given, from zero.
+    first: usize,
+    second: usize,
+    shared: Range<u64>,
+}
+
+impl Display for Overlap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "lines {} and {} both map {}..{}",
+            self.first + 1,
+            self.second + 1,
+            self.shared.start,
+            self.shared.end
+        )
+    }
+}
+
 impl Map {
+    fn new(kind: MapKind, mut lines: Vec<MapLine>) -> Result<Self, Overlap> {
+        let mut order: Vec<usize> = (0..lines.len()).collect();
+        order.sort_by_key(|&i| lines[i].source.start);
+        for pair in order.windows(2) {
+            let (a, b) = (&lines[pair[0]], &lines[pair[1]]);
+            if a.source.end > b.source.start {
+                return Err(Overlap {
+                    first: pair[0].min(pair[1]),
+                    second: pair[0].max(pair[1]),
+                    shared: b.source.start..a.source.end.min(b.source.end),
+                });
+            }
+        }
+
+        lines.sort_by_key(|line| line.source.start);
+        let mut merged: Vec<MapLine> = Vec::with_capacity(lines.len());
+        for line in lines {
+            match merged.last_mut() {
+                Some(last)
+                    if last.source.end == line.source.start && last.dest.end == line.dest.start =>
+                {
+                    last.source.end = line.source.end;
+                    last.dest.end = line.dest.end;
+                }
+                _ => merged.push(line),
+            }
+        }
+        Ok(Self {
+            kind,
+            lines: merged,
+        })
+    }
+
+    /// Index of the first line that ends after `value`.
+    fn index_of(&self, value: u64) -> usize {
+        self.lines.partition_point(|line| line.source.end <= value)
+    }
+
     fn translate(&self, src: u64) -> u64 {
         self.lines
-            .iter()
-            .find(|ml| ml.in_source(src))
-            .map(|line| line.translate(src))
+            .get(self.index_of(src))
+            .and_then(|line| line.translate(src))
             .unwrap_or(src)
     }
 
-    // From u/legobmw99 on Reddit.
-    fn translate_range(&self, range: Range<u64>) -> Vec<Range<u64>> {
+    fn translate_range(&self, range: Range<u64>) -> Result<Vec<Range<u64>>, &'static str> {
+        if range.is_empty() {
+            return Err("Range to translate is empty.");
+        }
         let mut mapped = Vec::new();
-        let mut remaining = vec![range];
+        let mut start = range.start;
+        for line in &self.lines[self.index_of(start)..] {
+            if start >= range.end {
+                break;
+            }
+            // Values before this line aren't covered by any line.
+            if start < line.source.start {
+                let end = line.source.start.min(range.end);
+                mapped.push(start..end);
+                start = end;
+                if start >= range.end {
+                    break;
+                }
+            }
+            let end = line.source.end.min(range.end);
+            mapped.push(
+                line.translate_range(start..end)
+                    .ok_or("Range strays outside its line.")?,
+            );
+            start = end;
+        }
+        if start < range.end {
+            mapped.push(start..range.end);
+        }
+        Ok(mapped)
+    }
+
+    /// Ranges not covered by any line, where values map to themselves.
+    fn identity_gaps(&self) -> Vec<Range<u64>> {
+        let mut gaps = Vec::new();
+        let mut next = 0;
+        for line in &self.lines {
+            if line.source.start > next {
+                gaps.push(next..line.source.start);
+            }
+            next = line.source.end;
+        }
+        if next < u64::MAX {
+            gaps.push(next..u64::MAX);
+        }
+        gaps
+    }
+}
 
-        while let Some(candidate) = remaining.pop() {
-            let mut did_something = false;
-            for m in &self.lines {
-                if let (Some(mapped_range), leftover_ranges) =
-                    m.translate_subrange(candidate.clone())
+impl Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gaps: Vec<String> = self
+            .identity_gaps()
+            .iter()
+            .map(|gap| format!("{}..{}", gap.start, gap.end))
+            .collect();
+        write!(
+            f,
+            "{}: {} lines, unchanged in {}",
+            self.kind,
+            self.lines.len(),
+            gaps.join(", ")
+        )
+    }
+}
+
+/// One piece of a [`PiecewiseMap`], which moves its source range so that
+/// it begins at `dest_start`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Piece {
+    source: Range<u64>,
+    dest_start: u64,
+}
+
+impl Piece {
+    fn translate(&self, value: u64) -> u64 {
+        self.dest_start + (value - self.source.start)
+    }
+
+    fn dest(&self) -> Range<u64> {
+        self.dest_start..self.translate(self.source.end)
+    }
+
+    fn shift(&self) -> i128 {
+        self.dest_start as i128 - self.source.start as i128
+    }
+}
+
+/// A map covering all of 0..u64::MAX as sorted, contiguous pieces, with
+/// the gaps between a [`Map`]'s lines filled in by identity pieces.
+///
+/// As the pieces are sorted, lookups are a binary search, and because
+/// composing two of these gives another, a whole chain of maps can be
+/// folded into one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PiecewiseMap {
+    pieces: Vec<Piece>,
+}
+
+impl PiecewiseMap {
+    fn identity() -> Self {
+        Self {
+            pieces: vec![Piece {
+                source: 0..u64::MAX,
+                dest_start: 0,
+            }],
+        }
+    }
+
+    fn from_map(map: &Map) -> Self {
+        let mut pieces = Vec::new();
+        let mut next = 0;
+        for line in &map.lines {
+            if line.source.start > next {
+                pieces.push(Piece {
+                    source: next..line.source.start,
+                    dest_start: next,
+                });
+            }
+            pieces.push(Piece {
+                source: line.source.clone(),
+                dest_start: line.dest.start,
+            });
+            next = line.source.end;
+        }
+        if next < u64::MAX {
+            pieces.push(Piece {
+                source: next..u64::MAX,
+                dest_start: next,
+            });
+        }
+        Self::coalesced(pieces)
+    }
+
+    /// Drop empty pieces and merge neighbours that move by the same amount.
+    fn coalesced(pieces: Vec<Piece>) -> Self {
+        let mut merged: Vec<Piece> = Vec::with_capacity(pieces.len());
+        for piece in pieces.into_iter().filter(|p| !p.source.is_empty()) {
+            match merged.last_mut() {
+                Some(last)
+                    if last.source.end == piece.source.start && last.shift() == piece.shift() =>
                 {
-                    mapped.push(mapped_range);
-                    remaining.extend(leftover_ranges);
-                    did_something = true;
-                    break;
+                    last.source.end = piece.source.end;
                 }
+                _ => merged.push(piece),
+            }
+        }
+        Self { pieces: merged }
+    }
+
+    /// Index of the piece containing `value`, which is past the end only
+    /// for u64::MAX itself.
+    fn index_of(&self, value: u64) -> usize {
+        self.pieces.partition_point(|p| p.source.end <= value)
+    }
+
+    fn translate(&self, value: u64) -> u64 {
+        self.pieces
+            .get(self.index_of(value))
+            .map_or(value, |p| p.translate(value))
+    }
+
+    /// Cut the range where it crosses from one piece into the next, so
+    /// that each part is moved as a block.
+    fn split_range(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        let mut parts = Vec::new();
+        let mut start = range.start;
+        for piece in &self.pieces[self.index_of(start)..] {
+            if start >= range.end {
+                break;
             }
-            // No need to convert the range.
-            if !did_something {
-                mapped.push(candidate);
+            let end = piece.source.end.min(range.end);
+            parts.push(start..end);
+            start = end;
+        }
+        parts
+    }
+
+    fn translate_range(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        self.split_range(range)
+            .into_iter()
+            .map(|part| {
+                let dest = self.translate(part.start);
+                dest..dest + (part.end - part.start)
+            })
+            .collect()
+    }
+
+    /// The map that applies `self` and then `next`.
+    fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut pieces = Vec::new();
+        for piece in &self.pieces {
+            let mut start = piece.source.start;
+            let mut index = next.index_of(piece.translate(start));
+            while start < piece.source.end {
+                let dest = piece.translate(start);
+                let Some(outer) = next.pieces.get(index) else {
+                    // Only u64::MAX is past the last piece, and it stays put.
+                    pieces.push(Piece {
+                        source: start..piece.source.end,
+                        dest_start: dest,
+                    });
+                    break;
+                };
+                let length = (piece.source.end - start).min(outer.source.end - dest);
+                pieces.push(Piece {
+                    source: start..start + length,
+                    dest_start: outer.translate(dest),
+                });
+                start += length;
+                index += 1;
             }
         }
-        mapped
+        Self::coalesced(pieces)
+    }
+
+    /// The map running the other way, if every value has exactly one
+    /// source.
+    fn invert(&self) -> Option<PiecewiseMap> {
+        let mut pieces: Vec<Piece> = self
+            .pieces
+            .iter()
+            .map(|p| Piece {
+                source: p.dest(),
+                dest_start: p.source.start,
+            })
+            .collect();
+        pieces.sort_by_key(|p| p.source.start);
+
+        // The pieces cover 0..u64::MAX between them, so they only tile it
+        // again after swapping if no two destinations overlap.
+        let mut next = 0;
+        for piece in &pieces {
+            if piece.source.start != next {
+                return None;
+            }
+            next = piece.source.end;
+        }
+        (next == u64::MAX).then(|| Self::coalesced(pieces))
+    }
+}
+
+/// Fold a chain of maps, in order, into a single map.
+fn compose(maps: &[Map]) -> PiecewiseMap {
+    maps.iter()
+        .map(PiecewiseMap::from_map)
+        .fold(PiecewiseMap::identity(), |acc, map| acc.then(&map))
+}
+
+/// The maps, put in order from seed to location by the categories they
+/// translate between.
+#[derive(Debug)]
+struct Almanac {
+    maps: Vec<Map>,
+}
+
+impl Almanac {
+    /// Chain the maps, in whatever order they're given, so that each
+    /// starts from the category the one before it ends on.
+    fn new(mut maps: Vec<Map>) -> Result<Self, &'static str> {
+        let mut chain = Vec::with_capacity(maps.len());
+        let mut current = Element::Seed;
+        while current != Element::Location {
+            let mut from_current = (0..maps.len()).filter(|&i| maps[i].kind.from == current);
+            let index = match (from_current.next(), from_current.next()) {
+                (Some(index), None) => index,
+                (Some(_), Some(_)) => return Err("More than one map from the same category."),
+                (None, _) => return Err("No map continues the chain to location."),
+            };
+            let map = maps.swap_remove(index);
+            current = map.kind.to;
+            chain.push(map);
+        }
+        if !maps.is_empty() {
+            return Err("Map is not on the chain from seed to location.");
+        }
+        Ok(Self { maps: chain })
+    }
+
+    /// Position of the map translating from this category.
+    fn position_from(&self, element: Element) -> Option<usize> {
+        self.maps.iter().position(|m| m.kind.from == element)
+    }
+
+    /// Position of the map translating to this category.
+    fn position_to(&self, element: Element) -> Option<usize> {
+        self.maps.iter().position(|m| m.kind.to == element)
+    }
+
+    /// A single map between any two categories. Going back up the chain
+    /// (say, location to seed) needs every map along the way to be
+    /// one-to-one.
+    fn query(&self, from: Element, to: Element) -> Result<PiecewiseMap, &'static str> {
+        if from == to {
+            return Ok(PiecewiseMap::identity());
+        }
+        let missing = "Category is not in the almanac.";
+        let (start, end) = match (self.position_from(from), self.position_to(to)) {
+            (Some(start), Some(end)) if start <= end => (start, end),
+            _ => {
+                let start = self.position_from(to).ok_or(missing)?;
+                let end = self.position_to(from).ok_or(missing)?;
+                return compose(&self.maps[start..=end])
+                    .invert()
+                    .ok_or("Map is not one-to-one, so can't be reversed.");
+            }
+        };
+        Ok(compose(&self.maps[start..=end]))
+    }
+
+    /// The value in every category on the way from seed to location.
+    fn trace(&self, seed: u64) -> Vec<(Element, u64)> {
+        let mut value = seed;
+        let mut trace = vec![(Element::Seed, seed)];
+        for map in &self.maps {
+            value = map.translate(value);
+            trace.push((map.kind.to, value));
+        }
+        trace
+    }
+
+    /// Find the seed with the lowest location.
+    ///
+    /// A single seed is explained as a one-seed range, so a seed of
+    /// u64::MAX is rejected rather than overflowing.
+    fn lowest(&self, seeds: &[Seed]) -> Result<Explanation, &'static str> {
+        let forward = self.query(Element::Seed, Element::Location)?;
+        let mut candidates = Vec::new();
+        for seed in seeds {
+            match seed {
+                Seed::Single(s) => {
+                    let end = s.checked_add(1).ok_or("Seed has no room for a range.")?;
+                    candidates.push((None, *s..end));
+                }
+                Seed::Range(r) => candidates.extend(
+                    forward
+                        .split_range(r.clone())
+                        .into_iter()
+                        .map(|part| (Some(r.clone()), part)),
+                ),
+            }
+        }
+        candidates
+            .into_iter()
+            .min_by_key(|(_, part)| forward.translate(part.start))
+            .map(|(from_range, seeds)| Explanation {
+                trace: self.trace(seeds.start),
+                from_range,
+                seeds,
+            })
+            .ok_or("There are no seeds.")
+    }
+}
+
+/// Where the lowest location comes from.
+#[derive(Debug, PartialEq, Eq)]
+struct Explanation {
+    /// The seed range the lowest seed belongs to, when seeds are ranges.
+    from_range: Option<Range<u64>>,
+    /// Seeds, starting with the lowest, that move as a block and so land
+    /// on consecutive locations.
+    seeds: Range<u64>,
+    trace: Vec<(Element, u64)>,
+}
+
+impl Explanation {
+    fn location(&self) -> u64 {
+        self.trace.last().unwrap().1
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut out = format!(
+            "Location {} is the lowest, from seed {}.\n",
+            self.location(),
+            self.seeds.start
+        );
+        if let Some(range) = &self.from_range {
+            writeln!(
+                out,
+                "Seeds {}..{} of the range {}..{} land on the locations after it.",
+                self.seeds.start, self.seeds.end, range.start, range.end
+            )?;
+        }
+        let steps: Vec<String> = self
+            .trace
+            .iter()
+            .map(|(element, value)| format!("{element} {value}"))
+            .collect();
+        write!(out, "{}", steps.join(" -> "))?;
+        write!(f, "{out}")
     }
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 struct Location(u64);
 
+/// Translate a seed one map at a time, rather than through [`compose`].
 fn chain(seed: &Seed, maps: &[Map]) -> Location {
     let Seed::Single(mut value) = seed else {
         return Location(u64::MAX);
@@ -107,72 +612,127 @@ fn chain(seed: &Seed, maps: &[Map]) -> Location {
     Location(value)
 }
 
-// From u/legobmw99 on Reddit
-fn chain_ranges(seeds: &[Seed], maps: &[Map]) -> Location {
-    let mut current: Vec<Range<u64>> = seeds.iter().filter_map(|s| s.as_range()).cloned().collect();
+/// Translate seed ranges one map at a time, rather than through
+/// [`compose`], coalescing the ranges after each map so that splitting
+/// them doesn't snowball. From u/legobmw99 on Reddit.
+fn chain_ranges(seeds: &[Seed], maps: &[Map]) -> Result<Location, &'static str> {
+    let mut current: IntervalSet<u64> = seeds.iter().filter_map(Seed::as_range).cloned().collect();
 
     for map in maps {
-        current = current
-            .iter()
-            .flat_map(|r| map.translate_range(r.clone()))
-            .collect();
+        let mut next = Vec::new();
+        for range in current.ranges() {
+            next.extend(map.translate_range(range.clone())?);
+        }
+        current = next.into_iter().collect();
     }
 
-    let value = current.into_iter().map(|r| r.start).min().unwrap();
-    Location(value)
+    current
+        .min()
+        .map(Location)
+        .ok_or("There are no seed ranges.")
 }
 
-fn parse_input_single_seeds(input: &str) -> IResult<&str, (Vec<Seed>, Vec<Map>)> {
-    let (input, seeds) = parse_single_seeds(input)?;
-    let (input, maps) = parse_maps(input)?;
-    Ok((input, (seeds, maps)))
+/// The almanac as written, with the maps in file order and the seed
+/// numbers not yet read as either single seeds or ranges.
+#[derive(Debug)]
+struct RawAlmanac {
+    seeds: Vec<u64>,
+    maps: Vec<RawMap>,
 }
 
-fn parse_input_seed_ranges(input: &str) -> IResult<&str, (Vec<Seed>, Vec<Map>)> {
-    let (input, seeds) = parse_seed_ranges(input)?;
-    let (input, maps) = parse_maps(input)?;
-    Ok((input, (seeds, maps)))
+/// A map as written, before its lines are checked.
+#[derive(Debug)]
+struct RawMap {
+    kind: MapKind,
+    lines: Vec<RawMapLine>,
 }
 
-fn parse_single_seeds(input: &str) -> IResult<&str, Vec<Seed>> {
-    let (input, _) = tag("seeds:")(input)?;
-    let (input, seeds) = many1(preceded(multispace1, digit1))(input)?;
-    let seeds = seeds
-        .into_iter()
-        .flat_map(str::parse)
-        .map(Seed::Single)
-        .collect();
-    Ok((input, seeds))
+/// A map line as written: destination start, source start, then length.
+#[derive(Debug)]
+struct RawMapLine {
+    dest_start: u64,
+    source_start: u64,
+    length: u64,
 }
 
-fn parse_one_seed_range(input: &str) -> IResult<&str, Seed> {
-    let (input, _) = multispace0(input)?;
-    let (input, start) = terminated(digit1, multispace1)(input)?;
-    let (input, length) = terminated(digit1, multispace1)(input)?;
+impl RawMap {
+    /// Check each line, then check that no two lines overlap.
+    fn validate(&self) -> Result<Map, String> {
+        let lines = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, raw)| {
+                MapLine::new(raw.source_start, raw.dest_start, raw.length)
+                    .map_err(|reason| format!("{} line {}: {reason}", self.kind, i + 1))
+            })
+            .collect::<Result<_, _>>()?;
+        Map::new(self.kind, lines).map_err(|overlap| format!("{}: {overlap}", self.kind))
+    }
+}
 
-    let start: u64 = start.parse().unwrap();
-    let length: u64 = length.parse().unwrap();
+impl RawAlmanac {
+    /// Check every map, reporting each one that's invalid.
+    fn maps(&self) -> Result<Vec<Map>, String> {
+        let (maps, errors): (Vec<_>, Vec<_>) =
+            self.maps.iter().map(RawMap::validate).partition_result();
+        if errors.is_empty() {
+            Ok(maps)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
 
-    Ok((input, Seed::Range(start..start + length)))
-}
+    /// Check every map, then chain them from seed to location.
+    fn almanac(&self) -> Result<Almanac, String> {
+        Almanac::new(self.maps()?).map_err(str::to_owned)
+    }
 
-fn parse_seed_ranges(input: &str) -> IResult<&str, Vec<Seed>> {
-    let (input, _) = tag("seeds: ")(input)?;
-    many1(parse_one_seed_range)(input)
+    fn single_seeds(&self) -> Vec<Seed> {
+        self.seeds.iter().copied().map(Seed::Single).collect()
+    }
+
+    /// Read the seed numbers as pairs of range start and length.
+    fn seed_ranges(&self) -> Result<Vec<Seed>, &'static str> {
+        let pairs = self.seeds.chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err("Seed range is missing its length.");
+        }
+        pairs
+            .map(|pair| {
+                if pair[1] == 0 {
+                    return Err("Seed range is empty.");
+                }
+                let end = pair[0]
+                    .checked_add(pair[1])
+                    .ok_or("Seed range overflows.")?;
+                Ok(Seed::Range(pair[0]..end))
+            })
+            .collect()
+    }
 }
 
-fn parse_maps(input: &str) -> IResult<&str, Vec<Map>> {
-    many1(parse_single_map)(input)
+fn parse_input(input: &str) -> IResult<&str, RawAlmanac> {
+    let (input, _) = tuple((tag("seeds:"), space1))(input)?;
+    let (input, seeds) = separated_list1(space1, u64)(input)?;
+    let (input, maps) = many1(preceded(multispace1, parse_single_map))(input)?;
+    let (input, _) = tuple((multispace0, eof))(input)?;
+    Ok((input, RawAlmanac { seeds, maps }))
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 struct MapKind {
     from: Element,
     to: Element,
 }
 
-#[derive(Debug, Clone)]
+impl Display for MapKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-to-{}", self.from, self.to)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Element {
     Seed,
     Soil,
@@ -184,6 +744,22 @@ enum Element {
     Location,
 }
 
+impl Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Element::Seed => "seed",
+            Element::Soil => "soil",
+            Element::Fertilizer => "fertilizer",
+            Element::Water => "water",
+            Element::Light => "light",
+            Element::Temperature => "temperature",
+            Element::Humidity => "humidity",
+            Element::Location => "location",
+        };
+        write!(f, "{name}")
+    }
+}
+
 fn parse_map_element(input: &str) -> IResult<&str, Element> {
     use Element::*;
     alt((
@@ -211,90 +787,97 @@ fn parse_map_type(input: &str) -> IResult<&str, MapKind> {
     ))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct MapLine {
     source: Range<u64>,
     dest: Range<u64>,
 }
 
 impl MapLine {
-    fn new(source_start: u64, dest_start: u64, length: u64) -> MapLine {
-        Self {
-            source: source_start..source_start + length,
-            dest: dest_start..dest_start + length,
+    /// A line covering `length` values, which can't be zero, and which
+    /// must end by u64::MAX on both sides.
+    fn new(source_start: u64, dest_start: u64, length: u64) -> Result<MapLine, &'static str> {
+        if length == 0 {
+            return Err("Map line is empty.");
         }
+        let source_end = source_start
+            .checked_add(length)
+            .ok_or("Map line source overflows.")?;
+        let dest_end = dest_start
+            .checked_add(length)
+            .ok_or("Map line destination overflows.")?;
+        Ok(Self {
+            source: source_start..source_end,
+            dest: dest_start..dest_end,
+        })
     }
 
     fn in_source(&self, value: u64) -> bool {
         self.source.contains(&value)
     }
 
-    fn translate(&self, value: u64) -> u64 {
-        self.dest.start + (value - self.source.start)
-    }
-
-    fn try_translate(&self, value: u64) -> Option<u64> {
-        if self.in_source(value) {
-            Some(self.translate(value))
-        } else {
-            None
-        }
+    /// Where `value` goes, or `None` if it isn't covered by this line.
+    fn translate(&self, value: u64) -> Option<u64> {
+        self.in_source(value)
+            .then(|| self.dest.start + (value - self.source.start))
     }
 
-    /// From u/legobmw99 on Reddit
-    fn translate_subrange(&self, r: Range<u64>) -> (Option<Range<u64>>, Vec<Range<u64>>) {
-        let mapped_start = self.try_translate(r.start);
-        let mapped_end = self.try_translate(r.end - 1).map(|v| v + 1);
-
-        let mapped = match (mapped_start, mapped_end) {
-            (None, None) => None,
-            (None, Some(end)) => Some(self.dest.start..end),
-            (Some(start), None) => Some(start..self.dest.end),
-            (Some(start), Some(end)) => Some(start..end),
-        };
-
-        let mut remaining = Vec::new();
-        if mapped.is_some() {
-            if self.source.start > r.start {
-                remaining.push(r.start..self.source.start);
-            }
-            if self.source.end < r.end {
-                remaining.push(self.source.end..r.end);
-            }
+    /// Where a range goes, or `None` if it's empty or not entirely
+    /// covered by this line.
+    fn translate_range(&self, range: Range<u64>) -> Option<Range<u64>> {
+        if range.is_empty() || range.end > self.source.end {
+            return None;
         }
-        (mapped, remaining)
+        let start = self.translate(range.start)?;
+        // The range is no longer than the rest of the line, so this stays
+        // within the destination.
+        Some(start..start + (range.end - range.start))
     }
 }
 
-fn parse_map_line(input: &str) -> IResult<&str, MapLine> {
-    let (input, dest_range_start) = terminated(digit1, multispace1)(input)?;
-    let (input, source_range_start) = terminated(digit1, multispace1)(input)?;
-    let (input, range_length) = terminated(digit1, multispace1)(input)?;
-    let line = MapLine::new(
-        source_range_start.parse().unwrap(),
-        dest_range_start.parse().unwrap(),
-        range_length.parse().unwrap(),
-    );
-    Ok((input, line))
+fn parse_map_line(input: &str) -> IResult<&str, RawMapLine> {
+    let (input, (dest_start, _, source_start, _, length, _)) =
+        tuple((u64, space1, u64, space1, u64, space0))(input)?;
+    Ok((
+        input,
+        RawMapLine {
+            dest_start,
+            source_start,
+            length,
+        },
+    ))
 }
 
-fn parse_single_map(input: &str) -> IResult<&str, Map> {
-    let (input, _) = multispace0(input)?;
+fn parse_single_map(input: &str) -> IResult<&str, RawMap> {
     let (input, kind) = parse_map_type(input)?;
-    let (input, _) = tag(" map:\n")(input)?;
-    let (input, lines) = many1(parse_map_line)(input)?;
-
-    let map = Map { kind, lines };
-    Ok((input, map))
+    let (input, _) = tuple((tag(" map:"), space0, line_ending))(input)?;
+    let (input, lines) = separated_list1(line_ending, parse_map_line)(input)?;
+    Ok((input, RawMap { kind, lines }))
 }
 
 #[cfg(test)]
 mod test {
-    use crate::day_05::chain_ranges;
+    use crate::{
+        day_05::chain_ranges,
+        utils::{rng::Lcg, IntervalSet},
+    };
 
     use super::{
-        chain, parse_input_seed_ranges, parse_input_single_seeds, part_one, Location, Seed, INPUT,
+        chain, compose, describe_maps, parse_input, parse_single_map, part_one, part_two, Almanac,
+        Element, Explanation, Location, Map, MapKind, MapLine, Overlap, PiecewiseMap, Seed, INPUT,
     };
+    /// The seeds read as singles, and the maps.
+    fn parse_input_single_seeds(input: &str) -> (Vec<Seed>, Vec<Map>) {
+        let raw = parse_input(input).unwrap().1;
+        (raw.single_seeds(), raw.maps().unwrap())
+    }
+
+    /// The seeds read as ranges, and the maps.
+    fn parse_input_seed_ranges(input: &str) -> (Vec<Seed>, Vec<Map>) {
+        let raw = parse_input(input).unwrap().1;
+        (raw.seed_ranges().unwrap(), raw.maps().unwrap())
+    }
+
     const TEST_INPUT: &str = "\
 seeds: 79 14 55 13
 
@@ -332,7 +915,7 @@ humidity-to-location map:
 
     #[test]
     fn day5_parse_test_input() {
-        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT).unwrap().1;
+        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT);
         assert_eq!(
             &seeds,
             &[
@@ -347,7 +930,7 @@ humidity-to-location map:
 
     #[test]
     fn day5_test_translate() {
-        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT).unwrap().1;
+        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT);
         let soils: Vec<u64> = seeds
             .into_iter()
             .map(|s| maps[0].translate(*s.as_single().unwrap()))
@@ -357,7 +940,7 @@ humidity-to-location map:
 
     #[test]
     fn day5_test_locations() {
-        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT).unwrap().1;
+        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT);
         let locations: Vec<Location> = seeds.iter().map(|s| chain(s, &maps)).collect();
         assert_eq!(
             &locations,
@@ -367,26 +950,514 @@ humidity-to-location map:
 
     #[test]
     fn day5_test_min_location() {
-        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT).unwrap().1;
-        assert_eq!(part_one(&seeds, &maps), 35);
+        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT);
+        assert_eq!(part_one(&seeds, &compose(&maps)), 35);
     }
 
     #[test]
     fn day5_real_min_location() {
-        let (seeds, maps) = parse_input_single_seeds(INPUT).unwrap().1;
-        assert_eq!(part_one(&seeds, &maps), 486613012);
+        let (seeds, maps) = parse_input_single_seeds(INPUT);
+        assert_eq!(part_one(&seeds, &compose(&maps)), 486613012);
     }
 
     #[test]
     fn day5_test_parse_seed_ranges() {
-        let (seeds, _) = parse_input_seed_ranges(TEST_INPUT).unwrap().1;
+        let (seeds, _) = parse_input_seed_ranges(TEST_INPUT);
         assert_eq!(&seeds, &[Seed::Range(79..93), Seed::Range(55..68),]);
     }
 
     #[test]
     fn day5_test_calculate_with_ranges() {
-        let (seeds, maps) = parse_input_seed_ranges(TEST_INPUT).unwrap().1;
-        let min_location = chain_ranges(&seeds, &maps);
+        let (seeds, maps) = parse_input_seed_ranges(TEST_INPUT);
+        let min_location = chain_ranges(&seeds, &maps).unwrap();
         assert_eq!(min_location.0, 46);
     }
+
+    #[test]
+    fn day5_compose_matches_chain() {
+        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT);
+        let almanac = compose(&maps);
+        for value in 0..120 {
+            assert_eq!(
+                Location(almanac.translate(value)),
+                chain(&Seed::Single(value), &maps)
+            );
+        }
+        let locations: Vec<u64> = seeds
+            .iter()
+            .map(|s| almanac.translate(*s.as_single().unwrap()))
+            .collect();
+        assert_eq!(&locations, &[82, 43, 86, 35]);
+    }
+
+    #[test]
+    fn day5_composed_pieces_are_sorted_and_contiguous() {
+        let (_, maps) = parse_input_single_seeds(INPUT);
+        let almanac = compose(&maps);
+        assert_eq!(almanac.pieces.first().unwrap().source.start, 0);
+        assert_eq!(almanac.pieces.last().unwrap().source.end, u64::MAX);
+        for pair in almanac.pieces.windows(2) {
+            assert_eq!(pair[0].source.end, pair[1].source.start);
+            assert_ne!(pair[0].shift(), pair[1].shift());
+        }
+    }
+
+    #[test]
+    fn day5_invert_map() {
+        let (_, maps) = parse_input_single_seeds(TEST_INPUT);
+        let seed_to_soil = PiecewiseMap::from_map(&maps[0]);
+        let inverse = seed_to_soil.invert().unwrap();
+        assert_eq!(inverse.translate(81), 79);
+        assert_eq!(inverse.translate(50), 98);
+        assert_eq!(inverse.translate(14), 14);
+    }
+
+    #[test]
+    fn day5_invert_composed_map() {
+        let (seeds, maps) = parse_input_single_seeds(INPUT);
+        let almanac = compose(&maps);
+        let inverse = almanac.invert().unwrap();
+        for seed in seeds.iter().filter_map(Seed::as_single) {
+            assert_eq!(inverse.translate(almanac.translate(*seed)), *seed);
+        }
+        assert_eq!(almanac.then(&inverse), PiecewiseMap::identity());
+        assert_eq!(inverse.then(&almanac), PiecewiseMap::identity());
+    }
+
+    #[test]
+    fn day5_invert_rejects_many_to_one() {
+        // 0..10 lands on 10..20, which is also left in place.
+        let map = PiecewiseMap::coalesced(vec![super::Piece {
+            source: 0..10,
+            dest_start: 10,
+        }]);
+        assert_eq!(map.invert(), None);
+    }
+
+    #[test]
+    fn day5_composed_ranges() {
+        let (seeds, maps) = parse_input_seed_ranges(TEST_INPUT);
+        assert_eq!(part_two(&seeds, &compose(&maps)).0, 46);
+        assert_eq!(
+            part_two(&seeds, &compose(&maps)),
+            chain_ranges(&seeds, &maps).unwrap()
+        );
+    }
+
+    #[test]
+    fn day5_real_composed_ranges() {
+        let (seeds, maps) = parse_input_seed_ranges(INPUT);
+        assert_eq!(
+            part_two(&seeds, &compose(&maps)),
+            chain_ranges(&seeds, &maps).unwrap()
+        );
+    }
+
+    #[test]
+    fn day5_staged_matches_run() {
+        assert_eq!(super::staged(), super::run());
+    }
+
+    #[test]
+    fn day5_almanac_any_order() {
+        let (seeds, mut maps) = parse_input_single_seeds(TEST_INPUT);
+        maps.reverse();
+        maps.swap(1, 4);
+        let almanac = Almanac::new(maps).unwrap();
+        let seed_to_location = almanac.query(Element::Seed, Element::Location).unwrap();
+        assert_eq!(part_one(&seeds, &seed_to_location), 35);
+    }
+
+    #[test]
+    fn day5_almanac_broken_chains() {
+        let (_, mut maps) = parse_input_single_seeds(TEST_INPUT);
+        maps.remove(3);
+        assert_eq!(
+            Almanac::new(maps).unwrap_err(),
+            "No map continues the chain to location."
+        );
+
+        let (_, mut maps) = parse_input_single_seeds(TEST_INPUT);
+        maps.push(
+            parse_single_map("seed-to-water map:\n1 2 3\n")
+                .unwrap()
+                .1
+                .validate()
+                .unwrap(),
+        );
+        assert_eq!(
+            Almanac::new(maps).unwrap_err(),
+            "More than one map from the same category."
+        );
+
+        let (_, mut maps) = parse_input_single_seeds(TEST_INPUT);
+        maps.push(
+            parse_single_map("location-to-seed map:\n1 2 3\n")
+                .unwrap()
+                .1
+                .validate()
+                .unwrap(),
+        );
+        assert_eq!(
+            Almanac::new(maps).unwrap_err(),
+            "Map is not on the chain from seed to location."
+        );
+    }
+
+    #[test]
+    fn day5_almanac_queries() {
+        let (_, maps) = parse_input_single_seeds(TEST_INPUT);
+        let almanac = Almanac::new(maps).unwrap();
+        // Seed 79 goes through soil 81, fertilizer 81, water 81, light 74,
+        // temperature 78 and humidity 78 to location 82.
+        let soil_to_humidity = almanac.query(Element::Soil, Element::Humidity).unwrap();
+        assert_eq!(soil_to_humidity.translate(81), 78);
+        let water_to_light = almanac.query(Element::Water, Element::Light).unwrap();
+        assert_eq!(water_to_light.translate(81), 74);
+        let same = almanac.query(Element::Light, Element::Light).unwrap();
+        assert_eq!(same.translate(74), 74);
+        let light_to_seed = almanac.query(Element::Light, Element::Seed).unwrap();
+        assert_eq!(light_to_seed.translate(74), 79);
+    }
+
+    #[test]
+    fn day5_real_location_to_seed() {
+        let (seeds, maps) = parse_input_single_seeds(INPUT);
+        let almanac = Almanac::new(maps).unwrap();
+        let forward = almanac.query(Element::Seed, Element::Location).unwrap();
+        let backward = almanac.query(Element::Location, Element::Seed).unwrap();
+        for &seed in seeds.iter().filter_map(Seed::as_single) {
+            assert_eq!(backward.translate(forward.translate(seed)), seed);
+        }
+    }
+
+    #[test]
+    fn day5_explain_single_seeds() {
+        let (seeds, maps) = parse_input_single_seeds(TEST_INPUT);
+        let almanac = Almanac::new(maps).unwrap();
+        let lowest = almanac.lowest(&seeds).unwrap();
+        assert_eq!(lowest.location(), 35);
+        assert_eq!(lowest.from_range, None);
+        assert_eq!(lowest.seeds, 13..14);
+        let expected = "\
+Location 35 is the lowest, from seed 13.
+seed 13 -> soil 13 -> fertilizer 52 -> water 41 -> light 34 -> temperature 34 -> humidity 35 -> location 35";
+        assert_eq!(lowest.to_string(), expected);
+
+        let last = almanac.lowest(&[Seed::Single(u64::MAX - 1)]).unwrap();
+        assert_eq!(last.seeds, u64::MAX - 1..u64::MAX);
+        assert_eq!(
+            almanac.lowest(&[Seed::Single(u64::MAX)]),
+            Err("Seed has no room for a range.")
+        );
+    }
+
+    #[test]
+    fn day5_explain_seed_ranges() {
+        let (seeds, maps) = parse_input_seed_ranges(TEST_INPUT);
+        let lowest = Almanac::new(maps).unwrap().lowest(&seeds).unwrap();
+        use Element::*;
+        assert_eq!(
+            lowest,
+            Explanation {
+                from_range: Some(79..93),
+                seeds: 82..92,
+                trace: vec![
+                    (Seed, 82),
+                    (Soil, 84),
+                    (Fertilizer, 84),
+                    (Water, 84),
+                    (Light, 77),
+                    (Temperature, 45),
+                    (Humidity, 46),
+                    (Location, 46),
+                ],
+            }
+        );
+        assert!(lowest
+            .to_string()
+            .contains("Seeds 82..92 of the range 79..93 land on the locations after it."));
+    }
+
+    #[test]
+    fn day5_real_explanations_match_answers() {
+        let (seeds, maps) = parse_input_single_seeds(INPUT);
+        let almanac = Almanac::new(maps).unwrap();
+        assert_eq!(almanac.lowest(&seeds).unwrap().location(), 486613012);
+        let (ranges, _) = parse_input_seed_ranges(INPUT);
+        assert_eq!(almanac.lowest(&ranges).unwrap().location(), 56931769);
+        assert_eq!(almanac.lowest(&[]), Err("There are no seeds."));
+    }
+
+    fn seed_to_soil() -> MapKind {
+        MapKind {
+            from: Element::Seed,
+            to: Element::Soil,
+        }
+    }
+
+    #[test]
+    fn day5_reject_overlapping_lines() {
+        let lines = vec![
+            MapLine::new(0, 100, 10).unwrap(),
+            MapLine::new(50, 200, 10).unwrap(),
+            MapLine::new(5, 300, 10).unwrap(),
+        ];
+        let overlap = Map::new(seed_to_soil(), lines).unwrap_err();
+        assert_eq!(
+            overlap,
+            Overlap {
+                first: 0,
+                second: 2,
+                shared: 5..10
+            }
+        );
+        assert_eq!(overlap.to_string(), "lines 1 and 3 both map 5..10");
+
+        let raw = parse_single_map("seed-to-soil map:\n0 0 10\n100 5 10\n")
+            .unwrap()
+            .1;
+        assert_eq!(
+            raw.validate().unwrap_err(),
+            "seed-to-soil: lines 1 and 2 both map 5..10"
+        );
+    }
+
+    #[test]
+    fn day5_report_every_invalid_map() {
+        let input = TEST_INPUT
+            .replace("50 98 2", "50 97 2")
+            .replace("88 18 7", "88 18 8");
+        let raw = parse_input(&input).unwrap().1;
+        let expected = "\
+seed-to-soil: lines 1 and 2 both map 97..98
+water-to-light: lines 1 and 2 both map 25..26";
+        assert_eq!(raw.maps().unwrap_err(), expected);
+        assert_eq!(raw.almanac().unwrap_err(), expected);
+
+        let report = describe_maps(&raw);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "seed-to-soil: lines 1 and 2 both map 97..98");
+        assert!(lines[1].starts_with("soil-to-fertilizer: 2 lines"));
+        assert_eq!(lines[3], "water-to-light: lines 1 and 2 both map 25..26");
+    }
+
+    #[test]
+    fn day5_normalise_lines() {
+        let lines = vec![
+            MapLine::new(20, 120, 10).unwrap(),
+            MapLine::new(0, 50, 5).unwrap(),
+            MapLine::new(10, 110, 10).unwrap(),
+        ];
+        let map = Map::new(seed_to_soil(), lines).unwrap();
+        assert_eq!(map.lines.len(), 2);
+        assert_eq!(
+            (map.lines[0].source.clone(), map.lines[0].dest.clone()),
+            (0..5, 50..55)
+        );
+        assert_eq!(
+            (map.lines[1].source.clone(), map.lines[1].dest.clone()),
+            (10..30, 110..130)
+        );
+        assert_eq!(map.identity_gaps(), vec![5..10, 30..u64::MAX]);
+        assert_eq!(
+            map.to_string(),
+            format!(
+                "seed-to-soil: 2 lines, unchanged in 5..10, 30..{}",
+                u64::MAX
+            )
+        );
+    }
+
+    #[test]
+    fn day5_translate_by_binary_search() {
+        let lines = vec![
+            MapLine::new(20, 120, 10).unwrap(),
+            MapLine::new(0, 50, 5).unwrap(),
+        ];
+        let map = Map::new(seed_to_soil(), lines).unwrap();
+        let expected = |v: u64| match v {
+            0..=4 => v + 50,
+            20..=29 => v + 100,
+            _ => v,
+        };
+        for value in 0..40 {
+            assert_eq!(map.translate(value), expected(value));
+        }
+        assert_eq!(
+            map.translate_range(3..35).unwrap(),
+            vec![53..55, 5..20, 120..130, 30..35]
+        );
+        assert_eq!(map.translate_range(22..25).unwrap(), vec![122..125]);
+        assert_eq!(map.translate_range(40..50).unwrap(), vec![40..50]);
+    }
+
+    #[test]
+    fn day5_real_identity_gaps() {
+        let (_, maps) = parse_input_single_seeds(INPUT);
+        let almanac = Almanac::new(maps).unwrap();
+        assert_eq!(almanac.maps[0].identity_gaps(), vec![4240966408..u64::MAX]);
+        for map in &almanac.maps {
+            for value in map.identity_gaps().iter().map(|gap| gap.start) {
+                assert_eq!(map.translate(value), value);
+            }
+        }
+    }
+
+    #[test]
+    fn day5_parse_line_endings() {
+        let (_, maps) = parse_input_single_seeds(TEST_INPUT);
+        // The last line has no newline after it, but still counts.
+        assert_eq!(maps[6].lines.len(), 2);
+
+        let expected = compose(&maps);
+        for input in [
+            format!("{TEST_INPUT}\n"),
+            format!("{TEST_INPUT}\n\n\n"),
+            TEST_INPUT.replace('\n', "\r\n"),
+            format!("{}\r\n", TEST_INPUT.replace('\n', "\r\n")),
+            TEST_INPUT.replace('\n', " \n"),
+        ] {
+            let (seeds, maps) = parse_input_single_seeds(&input);
+            assert_eq!(seeds.len(), 4);
+            assert_eq!(compose(&maps), expected);
+        }
+    }
+
+    #[test]
+    fn day5_parse_rejects_trailing_junk() {
+        assert!(parse_input(&format!("{TEST_INPUT}\nnonsense")).is_err());
+    }
+
+    #[test]
+    fn day5_interpret_seeds() {
+        let raw = parse_input("seeds: 1 2 3\n\nseed-to-location map:\n0 1 1")
+            .unwrap()
+            .1;
+        assert_eq!(raw.single_seeds().len(), 3);
+        assert_eq!(raw.seed_ranges(), Err("Seed range is missing its length."));
+
+        let input = format!("seeds: {} 2\n\nseed-to-location map:\n0 1 1", u64::MAX);
+        let raw = parse_input(&input).unwrap().1;
+        assert_eq!(raw.seed_ranges(), Err("Seed range overflows."));
+    }
+
+    #[test]
+    fn day5_invert_whole_test_almanac() {
+        let (_, maps) = parse_input_single_seeds(TEST_INPUT);
+        let inverse = compose(&maps).invert().unwrap();
+        assert_eq!(inverse.translate(46), 82);
+    }
+
+    #[test]
+    fn day5_checked_map_lines() {
+        assert_eq!(MapLine::new(5, 10, 0).unwrap_err(), "Map line is empty.");
+        assert_eq!(
+            MapLine::new(u64::MAX - 1, 0, 2).unwrap_err(),
+            "Map line source overflows."
+        );
+        assert_eq!(
+            MapLine::new(0, u64::MAX, 1).unwrap_err(),
+            "Map line destination overflows."
+        );
+        let line = MapLine::new(u64::MAX - 10, 0, 10).unwrap();
+        assert_eq!(line.translate(u64::MAX - 1), Some(9));
+        assert_eq!(line.translate(u64::MAX), None);
+        assert_eq!(line.translate(0), None);
+        assert_eq!(line.translate_range(u64::MAX - 3..u64::MAX), Some(7..10));
+        assert_eq!(line.translate_range(u64::MAX - 3..u64::MAX - 3), None);
+        assert_eq!(line.translate_range(u64::MAX - 11..u64::MAX), None);
+
+        let empty = parse_single_map("seed-to-soil map:\n5 5 5\n0 0 0")
+            .unwrap()
+            .1;
+        assert_eq!(
+            empty.validate().unwrap_err(),
+            "seed-to-soil line 2: Map line is empty."
+        );
+        let overflowing = format!("seed-to-soil map:\n0 {} 2", u64::MAX);
+        let overflowing = parse_single_map(&overflowing).unwrap().1;
+        assert_eq!(
+            overflowing.validate().unwrap_err(),
+            "seed-to-soil line 1: Map line source overflows."
+        );
+    }
+
+    #[test]
+    fn day5_checked_ranges() {
+        let map = Map::new(seed_to_soil(), vec![MapLine::new(10, 20, 5).unwrap()]).unwrap();
+        assert_eq!(
+            map.translate_range(7..7),
+            Err("Range to translate is empty.")
+        );
+        // Past every line, so left where it is.
+        let near_max = u64::MAX - 2..u64::MAX;
+        assert_eq!(map.translate_range(near_max.clone()).unwrap(), [near_max]);
+        assert_eq!(map.translate(u64::MAX), u64::MAX);
+
+        let raw = parse_input("seeds: 1 0\n\nseed-to-location map:\n0 1 1")
+            .unwrap()
+            .1;
+        assert_eq!(raw.seed_ranges(), Err("Seed range is empty."));
+    }
+
+    /// A map with lines scattered somewhere in base..base + 200, sending
+    /// values somewhere in base..base + 1000.
+    fn random_map(rng: &mut Lcg, base: u64) -> Map {
+        let mut lines = Vec::new();
+        let mut next = base + rng.below(20);
+        while next < base + 200 {
+            let length = 1 + rng.below(30);
+            lines.push(MapLine::new(next, base + rng.below(800), length).unwrap());
+            next += length + rng.below(15);
+        }
+        Map::new(seed_to_soil(), lines).unwrap()
+    }
+
+    #[test]
+    fn day5_range_translation_matches_values() {
+        let mut rng = Lcg(2023);
+        for round in 0..300 {
+            // Half the maps sit right up against u64::MAX.
+            let base = if round % 2 == 0 { 0 } else { u64::MAX - 1000 };
+            let map = random_map(&mut rng, base);
+            let piecewise = PiecewiseMap::from_map(&map);
+            for _ in 0..10 {
+                let start = base + rng.below(240);
+                let range = start..start + 1 + rng.below(60);
+
+                let by_value: IntervalSet<u64> = range
+                    .clone()
+                    .map(|v| map.translate(v))
+                    .map(|v| v..v + 1)
+                    .collect();
+                let mapped = map.translate_range(range.clone()).unwrap();
+                let length: u64 = mapped.iter().map(|r| r.end - r.start).sum();
+                assert_eq!(length, range.end - range.start);
+                assert_eq!(mapped.into_iter().collect::<IntervalSet<u64>>(), by_value);
+
+                let composed: IntervalSet<u64> = piecewise
+                    .translate_range(range.clone())
+                    .into_iter()
+                    .collect();
+                assert_eq!(composed, by_value);
+                for v in range {
+                    assert_eq!(piecewise.translate(v), map.translate(v));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn day5_composition_matches_values() {
+        let mut rng = Lcg(5);
+        for _ in 0..100 {
+            let (first, second) = (random_map(&mut rng, 0), random_map(&mut rng, 0));
+            let composed = PiecewiseMap::from_map(&first).then(&PiecewiseMap::from_map(&second));
+            for v in 0..1100 {
+                assert_eq!(composed.translate(v), second.translate(first.translate(v)));
+            }
+        }
+    }
 }
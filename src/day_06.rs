@@ -1,3 +1,5 @@
+use std::{fmt::Write, ops::RangeInclusive};
+
 use crate::utils;
 
 const INPUT: &str = include_str!("input/2023_06.txt");
@@ -6,6 +8,36 @@ pub fn run() -> String {
     utils::both(part_one(INPUT), part_two(INPUT))
 }
 
+/// A table of results for each of the part one races.
+pub fn table() -> String {
+    results_table(&parse_multiple_races(INPUT), &Physics::default())
+}
+
+fn results_table(races: &[Race], physics: &Physics) -> String {
+    let mut out = String::from("Time\tRecord\tHolds\tWays\tBest\tDistance\tMargin\n");
+    for race in races {
+        let result = race.result(physics);
+        let holds = result
+            .winning_holds
+            .as_ref()
+            .map_or("none".to_owned(), |h| format!("{}-{}", h.start(), h.end()));
+        writeln!(
+            out,
+            "{}\t{}\t{holds}\t{}\t{}\t{}\t{}",
+            race.time_limit,
+            race.distance_record,
+            result.ways_to_win(),
+            result.best_hold,
+            result.best_distance,
+            result.margin(),
+        )
+        .unwrap();
+    }
+    let product: u64 = races.iter().map(|r| r.ways_to_win_with(physics)).product();
+    write!(out, "Product of ways to win: {product}").unwrap();
+    out
+}
+
 fn part_one(input: &str) -> u64 {
     parse_multiple_races(input)
         .into_iter()
@@ -19,64 +51,228 @@ fn part_two(input: &str) -> u64 {
 
 #[derive(Debug, PartialEq)]
 struct Race {
-    time_limit: f64,
-    distance_record: f64,
+    time_limit: u64,
+    distance_record: u64,
 }
 
 impl Race {
-    #[allow(dead_code)]
-    fn from_pair((time_limit, distance_record): (f64, f64)) -> Self {
+    fn from_pair((time_limit, distance_record): (u64, u64)) -> Self {
         Self {
             time_limit,
             distance_record,
         }
     }
 
-    /// From mnvr on GitHub.
+    #[cfg(test)]
+    fn distance(&self, hold_time: u64) -> u128 {
+        hold_time as u128 * self.time_limit.saturating_sub(hold_time) as u128
+    }
+
+    #[cfg(test)]
+    fn beats_record(&self, hold_time: u64) -> bool {
+        self.distance(hold_time) > self.distance_record as u128
+    }
+
+    /// The shortest and longest hold times that beat the record, or `None`
+    /// if the record can't be beaten. After mnvr on GitHub.
     ///
-    /// Quadratic equation:
+    /// Beating the record means h(T - h) > D, so the bounds lie between
+    /// the roots of the quadratic:
     ///  T = time limit
     ///  h = hold time (speed)
     ///  D = distance record
     ///      -h^2 + Th - D
     ///
-    ///  Quadratic formula, to solve for h:
-    ///      ± (√ T^2 - 4D) + T
-    #[allow(unused, dead_code)]
-    fn calculate_hold_time_bounds(&self) -> (u64, u64) {
-        let t = self.time_limit;
-        let d = self.distance_record;
-
-        let lower = (-(t * t - 4.0 * d).sqrt() + t) / 2.0;
-        let upper = ((t * t - 4.0 * d).sqrt() + t) / 2.0;
-
-        // Handle the case where lower & upper are already integers,
-        // by reducing the range inwards by 1 on each side.
-        let lower = {
-            let ceil = lower.ceil();
-            (if ceil != lower { ceil } else { lower + 1.0 }) as u64
-        };
-        let upper = {
-            let floor = upper.floor();
-            (if floor != upper { floor } else { upper - 1.0 }) as u64
-        };
+    /// Solved with an integer square root instead of floating point, which
+    /// can't represent the part two numbers exactly. The root is only an
+    /// estimate of the bound, so it's nudged until it's exact.
+    ///
+    /// Only handles the standard physics. `winning_holds` solves every
+    /// configuration and must agree with this one on the standard rules.
+    #[cfg(test)]
+    fn calculate_hold_time_bounds(&self) -> Option<(u64, u64)> {
+        let t = self.time_limit as u128;
+        let d = self.distance_record as u128;
+        let discriminant = (t * t).checked_sub(4 * d)?;
+        let mut lower = ((t - discriminant.isqrt()) / 2) as u64;
+        while lower <= self.time_limit / 2 && !self.beats_record(lower) {
+            lower += 1;
+        }
+        while lower > 0 && self.beats_record(lower - 1) {
+            lower -= 1;
+        }
+        if !self.beats_record(lower) {
+            return None;
+        }
+        // Distance is symmetric around half the time limit.
+        Some((lower, self.time_limit - lower))
+    }
+
+    fn ways_to_win(&self) -> u64 {
+        self.ways_to_win_with(&Physics::default())
+    }
+
+    fn ways_to_win_with(&self, physics: &Physics) -> u64 {
+        self.result(physics).ways_to_win()
+    }
+
+    fn result(&self, physics: &Physics) -> RaceResult {
+        let best_hold = physics.best_hold(self.time_limit);
+        RaceResult {
+            winning_holds: self.winning_holds(physics),
+            best_hold,
+            best_distance: physics.distance(self.time_limit, best_hold),
+            distance_record: self.distance_record,
+        }
+    }
 
-        (lower, upper)
+    fn wins(&self, physics: &Physics, hold_time: u64) -> bool {
+        let distance = physics.distance(self.time_limit, hold_time);
+        let record = self.distance_record as u128;
+        distance > record || (physics.ties_win && distance == record)
     }
 
+    /// Every hold time that wins the race, or `None` if it can't be won.
+    ///
+    /// Distance rises to a single peak and then falls, so the winning hold
+    /// times are one unbroken interval around the peak, and each end of it
+    /// can be found by binary search.
+    fn winning_holds(&self, physics: &Physics) -> Option<RangeInclusive<u64>> {
+        let peak = physics.best_hold(self.time_limit);
+        if !self.wins(physics, peak) {
+            return None;
+        }
+
+        let (mut lo, mut hi) = (0, peak);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.wins(physics, mid) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        let lower = lo;
+
+        let (mut lo, mut hi) = (peak, self.time_limit);
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            if self.wins(physics, mid) {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        Some(lower..=lo)
+    }
+}
+
+/// How a race can be won, and by how much.
+#[derive(Debug, PartialEq, Eq)]
+struct RaceResult {
+    winning_holds: Option<RangeInclusive<u64>>,
+    /// The hold time that travels furthest. If two do, the shorter one.
+    best_hold: u64,
+    best_distance: u128,
+    distance_record: u64,
+}
+
+impl RaceResult {
     fn ways_to_win(&self) -> u64 {
-        let (lower, upper) = self.calculate_hold_time_bounds();
-        upper - lower + 1
+        self.winning_holds
+            .as_ref()
+            .map_or(0, |holds| holds.end() - holds.start() + 1)
+    }
+
+    /// How far the best hold goes past the record, which is negative if
+    /// the record can't be reached.
+    fn margin(&self) -> i128 {
+        i128::try_from(self.best_distance).unwrap_or(i128::MAX) - self.distance_record as i128
+    }
+}
+
+/// How holding the button turns into distance, and what counts as a win.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Physics {
+    /// Speed gained for each millisecond the button is held.
+    acceleration: u64,
+    /// Top speed, if there is one.
+    max_speed: Option<u64>,
+    /// Milliseconds after the button is released before the boat moves.
+    start_delay: u64,
+    /// Whether matching the record is enough to win.
+    ties_win: bool,
+}
+
+impl Default for Physics {
+    /// The puzzle's rules: one unit of speed per millisecond, no limits,
+    /// and the record has to be beaten outright.
+    fn default() -> Self {
+        Self {
+            acceleration: 1,
+            max_speed: None,
+            start_delay: 0,
+            ties_win: false,
+        }
+    }
+}
+
+impl Physics {
+    fn speed(&self, hold_time: u64) -> u128 {
+        let speed = self.acceleration as u128 * hold_time as u128;
+        self.max_speed
+            .map_or(speed, |max_speed| speed.min(max_speed as u128))
+    }
+
+    /// Time left to move once the button is released and the delay is over.
+    fn moving_time(&self, time_limit: u64, hold_time: u64) -> u64 {
+        time_limit
+            .saturating_sub(hold_time)
+            .saturating_sub(self.start_delay)
+    }
+
+    /// How far the boat goes. This saturates rather than overflowing,
+    /// which is still enough to compare against a u64 record.
+    fn distance(&self, time_limit: u64, hold_time: u64) -> u128 {
+        self.speed(hold_time)
+            .saturating_mul(self.moving_time(time_limit, hold_time) as u128)
+    }
+
+    /// The hold time that travels furthest.
+    ///
+    /// Ignoring the top speed, distance is a·h(T' - h), where T' is the time
+    /// limit less the delay, which peaks at h = T'/2. Past the hold time
+    /// that reaches top speed, holding longer only loses moving time. So
+    /// the peak is at whichever of those comes first, rounded either way.
+    fn best_hold(&self, time_limit: u64) -> u64 {
+        let available = time_limit.saturating_sub(self.start_delay);
+        let (floor, ceil) = match self.max_speed {
+            _ if self.acceleration == 0 => (0, 0),
+            Some(max_speed)
+                if (max_speed as u128) * 2 < self.acceleration as u128 * available as u128 =>
+            {
+                (
+                    max_speed / self.acceleration,
+                    max_speed.div_ceil(self.acceleration),
+                )
+            }
+            _ => (available / 2, available.div_ceil(2)),
+        };
+        if self.distance(time_limit, ceil) > self.distance(time_limit, floor) {
+            ceil
+        } else {
+            floor
+        }
     }
 }
 
 fn parse_multiple_races(input: &str) -> Vec<Race> {
-    let [times, distances]: [Vec<f64>; 2] = input
+    let [times, distances]: [Vec<u64>; 2] = input
         .lines()
         .filter_map(|line| line.split_once(": "))
         .map(|(_prefix, numbers)| numbers.split_whitespace())
-        .map(|numbers| numbers.map(|s| s.parse::<f64>().unwrap()).collect())
-        .collect::<Vec<Vec<f64>>>()
+        .map(|numbers| numbers.map(|s| s.parse::<u64>().unwrap()).collect())
+        .collect::<Vec<Vec<u64>>>()
         .try_into()
         .expect("Couldn't parse input as two lines of numbers.");
 
@@ -88,15 +284,15 @@ fn parse_multiple_races(input: &str) -> Vec<Race> {
 }
 
 fn parse_single_race(input: &str) -> Race {
-    let [time_limit, distance_record]: [f64; 2] = input
+    let [time_limit, distance_record]: [u64; 2] = input
         .lines()
         .filter_map(|line| line.split_once(": "))
         .map(|(_prefix, line)| {
             line.chars()
                 .filter_map(|c| c.to_digit(10).map(|n| n as u64))
-                .fold(0, |acc, next| acc * 10 + next) as f64
+                .fold(0, |acc, next| acc * 10 + next)
         })
-        .collect::<Vec<f64>>()
+        .collect::<Vec<u64>>()
         .try_into()
         .expect("Couldn't parse input as two numbers.");
 
@@ -108,7 +304,9 @@ fn parse_single_race(input: &str) -> Race {
 
 #[cfg(test)]
 mod test {
-    use super::{parse_multiple_races, part_one, part_two, Race, INPUT};
+    use super::{
+        parse_multiple_races, part_one, part_two, results_table, Physics, Race, RaceResult, INPUT,
+    };
     const TEST_INPUT: &str = "\
 Time:      7  15   30
 Distance:  9  40  200";
@@ -119,9 +317,9 @@ Distance:  9  40  200";
         assert_eq!(
             races,
             vec![
-                Race::from_pair((7.0, 9.0)),
-                Race::from_pair((15.0, 40.0)),
-                Race::from_pair((30.0, 200.0))
+                Race::from_pair((7, 9)),
+                Race::from_pair((15, 40)),
+                Race::from_pair((30, 200))
             ]
         );
     }
@@ -129,10 +327,11 @@ Distance:  9  40  200";
     #[test]
     fn day6_test_calculate() {
         let (min_hold, max_hold) = Race {
-            time_limit: 7.0,
-            distance_record: 9.0,
+            time_limit: 7,
+            distance_record: 9,
         }
-        .calculate_hold_time_bounds();
+        .calculate_hold_time_bounds()
+        .unwrap();
         assert_eq!(min_hold, 2);
         assert_eq!(max_hold, 5);
     }
@@ -165,4 +364,170 @@ Distance:  9  40  200";
     fn day6_real_part_two() {
         assert_eq!(part_two(INPUT), 33149631);
     }
+
+    #[test]
+    fn day6_matches_brute_force() {
+        for time_limit in 0..60 {
+            for distance_record in 0..=(time_limit * time_limit / 4 + 2) {
+                let race = Race::from_pair((time_limit, distance_record));
+                let winners: Vec<u64> =
+                    (0..=time_limit).filter(|&h| race.beats_record(h)).collect();
+                let expected = winners
+                    .first()
+                    .map(|&lower| (lower, *winners.last().unwrap()));
+                assert_eq!(
+                    race.calculate_hold_time_bounds(),
+                    expected,
+                    "{time_limit} {distance_record}"
+                );
+                assert_eq!(race.ways_to_win(), winners.len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn day6_beyond_f64_precision() {
+        // T^2 needs more bits than an f64 has, so the floating point solver
+        // can't tell these records apart. The first leaves only the midpoint
+        // winning, and the second can't be beaten at all.
+        let time_limit: u64 = (1 << 32) + 2;
+        let best = (time_limit / 2).pow(2);
+        let race = Race::from_pair((time_limit, best - 1));
+        assert_eq!(
+            race.calculate_hold_time_bounds(),
+            Some((time_limit / 2, time_limit / 2))
+        );
+        let race = Race::from_pair((time_limit, best));
+        assert_eq!(race.calculate_hold_time_bounds(), None);
+        assert_eq!(race.ways_to_win(), 0);
+    }
+
+    #[test]
+    fn day6_winning_holds_matches_closed_form() {
+        let physics = Physics::default();
+        for time_limit in 0..60 {
+            for distance_record in 0..=(time_limit * time_limit / 4 + 2) {
+                let race = Race::from_pair((time_limit, distance_record));
+                let bounds = race
+                    .winning_holds(&physics)
+                    .map(|holds| (*holds.start(), *holds.end()));
+                assert_eq!(bounds, race.calculate_hold_time_bounds());
+            }
+        }
+    }
+
+    #[test]
+    fn day6_general_physics_matches_brute_force() {
+        for acceleration in 0..4 {
+            for max_speed in [None, Some(0), Some(1), Some(5), Some(12)] {
+                for start_delay in [0, 1, 3] {
+                    for ties_win in [false, true] {
+                        let physics = Physics {
+                            acceleration,
+                            max_speed,
+                            start_delay,
+                            ties_win,
+                        };
+                        for time_limit in 0..25 {
+                            for distance_record in 0..80 {
+                                let race = Race::from_pair((time_limit, distance_record));
+                                let winners: Vec<u64> = (0..=time_limit)
+                                    .filter(|&h| race.wins(&physics, h))
+                                    .collect();
+                                let expected = winners
+                                    .first()
+                                    .map(|&lower| lower..=*winners.last().unwrap());
+                                assert_eq!(
+                                    race.winning_holds(&physics),
+                                    expected,
+                                    "{physics:?} {race:?}"
+                                );
+                                assert_eq!(race.ways_to_win_with(&physics), winners.len() as u64);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn day6_physics_variants() {
+        let race = Race::from_pair((7, 9));
+        // Holding for 2 or 5 travels exactly 10, which only wins on a tie.
+        let ties = Physics {
+            ties_win: true,
+            ..Physics::default()
+        };
+        assert_eq!(Race::from_pair((7, 10)).winning_holds(&ties), Some(2..=5));
+        assert_eq!(Race::from_pair((7, 10)).ways_to_win(), 2);
+
+        // Double acceleration: 2h(7 - h) > 9 for h in 1..=6.
+        let fast = Physics {
+            acceleration: 2,
+            ..Physics::default()
+        };
+        assert_eq!(race.winning_holds(&fast), Some(1..=6));
+
+        // Capped at speed 3, holding for 3 or more gains nothing.
+        let capped = Physics {
+            max_speed: Some(3),
+            ..Physics::default()
+        };
+        assert_eq!(race.winning_holds(&capped), Some(2..=3));
+
+        // A one millisecond delay is like a shorter race.
+        let delayed = Physics {
+            start_delay: 1,
+            ..Physics::default()
+        };
+        assert_eq!(race.winning_holds(&delayed), None);
+        assert_eq!(Race::from_pair((7, 7)).winning_holds(&delayed), Some(2..=4));
+    }
+
+    #[test]
+    fn day6_huge_acceleration() {
+        // The best distance is far past u128::MAX, so it saturates.
+        let physics = Physics {
+            acceleration: u64::MAX,
+            ..Physics::default()
+        };
+        let result = Race::from_pair((u64::MAX, u64::MAX)).result(&physics);
+        assert_eq!(result.winning_holds, Some(1..=u64::MAX - 1));
+        assert_eq!(result.best_distance, u128::MAX);
+        assert_eq!(result.margin(), i128::MAX - u64::MAX as i128);
+    }
+
+    #[test]
+    fn day6_race_result() {
+        let result = Race::from_pair((7, 9)).result(&Physics::default());
+        assert_eq!(
+            result,
+            RaceResult {
+                winning_holds: Some(2..=5),
+                best_hold: 3,
+                best_distance: 12,
+                distance_record: 9,
+            }
+        );
+        assert_eq!(result.ways_to_win(), 4);
+        assert_eq!(result.margin(), 3);
+
+        let result = Race::from_pair((7, 20)).result(&Physics::default());
+        assert_eq!(result.winning_holds, None);
+        assert_eq!(result.ways_to_win(), 0);
+        assert_eq!(result.margin(), -8);
+    }
+
+    #[test]
+    fn day6_results_table() {
+        let table = results_table(&parse_multiple_races(TEST_INPUT), &Physics::default());
+        let expected = "\
+Time\tRecord\tHolds\tWays\tBest\tDistance\tMargin
+7\t9\t2-5\t4\t3\t12\t3
+15\t40\t4-11\t8\t7\t56\t16
+30\t200\t11-19\t9\t15\t225\t25
+Product of ways to win: 288";
+        assert_eq!(table, expected);
+    }
 }
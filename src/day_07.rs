@@ -1,6 +1,8 @@
 use counter::Counter;
+use itertools::Itertools;
 use std::{
-    cmp::Ordering,
+    cmp::Reverse,
+    fmt::{Display, Write},
     hash::{Hash, Hasher},
 };
 
@@ -9,136 +11,355 @@ use crate::utils;
 const INPUT: &str = include_str!("input/2023_07.txt");
 
 pub fn run() -> String {
-    let hands = parse_input(INPUT);
-    utils::both(part_one(hands.clone()), part_two(hands))
+    match parse_input(INPUT) {
+        Ok(hands) => utils::both(part_one(hands.clone()), part_two(hands)),
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Every hand in rank order, showing how it was classified and what it
+/// won, with jacks as jokers if `jokers` is set, and without recognising
+/// any of the hand types named in `without`.
+pub fn report(jokers: bool, without: &[String]) -> String {
+    let mut rules = if jokers {
+        Rules::jokers()
+    } else {
+        Rules::standard()
+    };
+    for name in without {
+        match HandType::from_name(name) {
+            // Every hand is at least a high card.
+            Some(HandType::HighCard) => return "HighCard can't be removed.".to_owned(),
+            Some(hand_type) => rules = rules.without_hand_type(hand_type),
+            None => return format!("Unknown hand type {name:?}."),
+        }
+    }
+    match parse_input(INPUT) {
+        Ok(hands) => ranking_report(&hands, &rules),
+        Err(e) => e.to_string(),
+    }
 }
 
-fn parse_input(input: &str) -> Vec<Hand> {
+fn ranking_report(hands: &[Hand], rules: &Rules) -> String {
+    let mut out = String::from("Rank\tCards\tPlayed\tType\t\tBid\tWinnings\n");
+    let mut total = 0;
+    for (idx, scored) in rank_hands(hands, rules).into_iter().enumerate() {
+        let rank = idx as u32 + 1;
+        let winnings = rank * scored.hand.bid;
+        total += winnings;
+        writeln!(
+            out,
+            "{rank}\t{}\t{}\t{:<12}\t{}\t{winnings}",
+            scored.hand.cards.iter().join(""),
+            scored.played.iter().join(""),
+            format!("{:?}", scored.hand_type),
+            scored.hand.bid,
+        )
+        .unwrap();
+    }
+    write!(out, "Total winnings: {total}").unwrap();
+    out
+}
+
+/// The most cards a hand can have and still pack into a strength key.
+const MAX_HAND_LENGTH: usize = 15;
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseError {
+    /// Line number in the input, starting from 1, or `None` if the
+    /// problem isn't with any one line.
+    line: Option<usize>,
+    reason: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.reason),
+            None => write!(f, "{}", self.reason),
+        }
+    }
+}
+
+fn parse_input(input: &str) -> Result<Vec<Hand>, ParseError> {
+    parse_hands(input, 5)
+}
+
+/// Parse hands of exactly `hand_length` cards, one per line, skipping
+/// blank lines. Any other malformed line is an error.
+fn parse_hands(input: &str, hand_length: usize) -> Result<Vec<Hand>, ParseError> {
+    if !(1..=MAX_HAND_LENGTH).contains(&hand_length) {
+        return Err(ParseError {
+            line: None,
+            reason: format!("Hands must have between 1 and {MAX_HAND_LENGTH} cards."),
+        });
+    }
     input
         .lines()
-        .filter_map(|line| line.split_once(' '))
-        .map(|(cards, bid)| {
-            let cards = cards
-                .chars()
-                .flat_map(Card::try_from)
-                .collect::<Vec<_>>()
-                .try_into()
-                .expect("Couldn't unpack parsed line into [Card; 5]");
-            let bid = bid.parse().unwrap();
-            Hand::new(cards, bid)
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            parse_hand(line, hand_length).map_err(|reason| ParseError {
+                line: Some(idx + 1),
+                reason,
+            })
         })
         .collect()
 }
 
-fn part_one(mut hands: Vec<Hand>) -> u32 {
-    hands.sort();
-    hands
+fn parse_hand(line: &str, hand_length: usize) -> Result<Hand, String> {
+    let Some((cards, bid)) = line.trim().split_once(' ') else {
+        return Err(format!("No space between cards and bid in {line:?}"));
+    };
+    let cards = cards
+        .chars()
+        .enumerate()
+        .map(|(idx, c)| {
+            Card::try_from(c).map_err(|_| format!("Unknown card {c:?} at position {}", idx + 1))
+        })
+        .collect::<Result<Vec<Card>, String>>()?;
+    if cards.len() != hand_length {
+        return Err(format!(
+            "Expected {hand_length} cards but found {}",
+            cards.len()
+        ));
+    }
+    let bid = bid
+        .trim()
+        .parse()
+        .map_err(|_| format!("Bid {bid:?} is not a number"))?;
+    Ok(Hand::new(cards, bid))
+}
+
+fn part_one(hands: Vec<Hand>) -> u32 {
+    winnings(hands, &Rules::standard())
+}
+
+fn part_two(hands: Vec<Hand>) -> u32 {
+    winnings(hands, &Rules::jokers())
+}
+
+fn winnings(hands: Vec<Hand>, rules: &Rules) -> u32 {
+    rank_hands(&hands, rules)
         .into_iter()
         .enumerate()
-        .map(|(idx, hand)| (idx as u32 + 1) * hand.bid)
+        .map(|(idx, scored)| (idx as u32 + 1) * scored.hand.bid)
         .sum()
 }
 
-fn part_two(hands: Vec<Hand>) -> u32 {
-    let hands = hands.into_iter().map(Hand::jack_to_joker).collect();
-    part_one(hands)
+/// Hands from weakest to strongest.
+fn rank_hands<'a>(hands: &'a [Hand], rules: &Rules) -> Vec<ScoredHand<'a>> {
+    let mut scored: Vec<ScoredHand> = hands.iter().map(|hand| rules.score(hand)).collect();
+    scored.sort_unstable_by_key(|s| s.strength);
+    scored
+}
+
+/// A hand with its strength under a set of rules packed into a single
+/// integer, so hands can be ranked with a plain integer sort.
+#[derive(Debug)]
+struct ScoredHand<'a> {
+    strength: u64,
+    /// The cards with any wildcards replaced by what they stand in for.
+    played: Vec<Card>,
+    hand_type: HandType,
+    hand: &'a Hand,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct Hand {
-    effective_cards: [Card; 5],
-    underlying_cards: [Card; 5],
+    cards: Vec<Card>,
     bid: u32,
 }
 
 impl Hand {
-    fn new(cards: [Card; 5], bid: u32) -> Self {
-        Hand {
-            effective_cards: cards,
-            underlying_cards: cards,
-            bid,
-        }
+    fn new(cards: Vec<Card>, bid: u32) -> Self {
+        Hand { cards, bid }
     }
 
-    fn find_replacement_card(cards: &[Card]) -> Card {
-        let mut counter: Counter<_> = cards.iter().collect();
-        let joker_count = counter.remove(&Card::Joker).unwrap_or_default();
-        if joker_count == 5 {
-            // All jokers, so just return the highest possible card.
-            Card::King
-        } else {
-            // Match jokers to the most-common non-joker card.
-            let &(&most_common, _) = counter.most_common().first().unwrap();
-            most_common
-        }
+    #[cfg(test)]
+    fn hand_type(&self, rules: &Rules) -> HandType {
+        rules.best_substitution(&self.cards).1
     }
+}
+
+/// How hands are scored, so that each variant of Camel Cards is just
+/// a different set of rules rather than a change to the hands.
+#[derive(Debug, Clone)]
+struct Rules {
+    /// Every card, from weakest to strongest, used to break ties.
+    card_order: Vec<Card>,
+    /// Cards that stand in for whichever card makes the strongest hand.
+    wildcards: Vec<Card>,
+    /// The hand types that are recognised, from weakest to strongest.
+    /// A hand is the strongest of these that its cards satisfy.
+    hand_types: Vec<HandType>,
+}
 
-    fn jack_to_joker(self) -> Self {
-        let underlying = self.underlying_cards.map(Card::jack_to_joker);
-        let replacement = Hand::find_replacement_card(&underlying);
-        let effective = underlying.map(|c| if c.is_joker() { replacement } else { c });
-        Hand {
-            effective_cards: effective,
-            underlying_cards: underlying,
-            bid: self.bid,
+impl Rules {
+    /// Part one: no wildcards, and aces high.
+    fn standard() -> Self {
+        use Card::*;
+        let mut card_order: Vec<Card> = (2..=10).map(Number).collect();
+        card_order.extend([Jack, Queen, King, Ace]);
+        use HandType::*;
+        let hand_types = vec![
+            HighCard,
+            OnePair,
+            TwoPair,
+            ThreeOfAKind,
+            FullHouse,
+            FourOfAKind,
+            FiveOfAKind,
+        ];
+        Self {
+            card_order,
+            wildcards: Vec::new(),
+            hand_types,
         }
     }
 
-    fn hand_type(&self) -> HandType {
-        let counter: Counter<&Card> = self.effective_cards.iter().collect();
-        let in_order = counter.most_common();
-        let (_, count_most_common) = in_order.first().unwrap();
-        match count_most_common {
-            5 => HandType::FiveOfAKind,
-            4 => HandType::FourOfAKind,
-            3 => {
-                if in_order.len() == 2 {
-                    // Three of a kind and a pair
-                    HandType::FullHouse
-                } else {
-                    // Three of a kind and two single cards
-                    HandType::ThreeOfAKind
-                }
-            }
-            2 => {
-                if in_order.len() == 3 {
-                    // Two pairs and one single card
-                    HandType::TwoPair
+    /// Part two: jacks are jokers, which are wild but the weakest card.
+    fn jokers() -> Self {
+        Self::standard().with_wildcard(Card::Jack)
+    }
+
+    /// Make a card wild, and the weakest card when breaking ties.
+    fn with_wildcard(mut self, card: Card) -> Self {
+        self.card_order.retain(|&c| c != card);
+        self.card_order.insert(0, card);
+        self.wildcards.push(card);
+        self
+    }
+
+    /// Stop recognising a hand type, so hands that would have been that
+    /// type drop to the strongest type they still satisfy.
+    fn without_hand_type(mut self, hand_type: HandType) -> Self {
+        self.hand_types.retain(|&t| t != hand_type);
+        self
+    }
+
+    fn is_wild(&self, card: &Card) -> bool {
+        self.wildcards.contains(card)
+    }
+
+    fn card_rank(&self, card: &Card) -> usize {
+        self.card_order.iter().position(|c| c == card).unwrap()
+    }
+
+    fn type_rank(&self, hand_type: &HandType) -> usize {
+        self.hand_types.iter().position(|t| t == hand_type).unwrap()
+    }
+
+    /// Type of a hand taking every card at face value.
+    fn plain_hand_type(&self, cards: &[Card]) -> HandType {
+        let counter: Counter<&Card> = cards.iter().collect();
+        let counts: Vec<usize> = counter.most_common().into_iter().map(|(_, n)| n).collect();
+        *self
+            .hand_types
+            .iter()
+            .rev()
+            .find(|t| t.satisfied_by(&counts))
+            .expect("Rules must recognise a hand type for every hand.")
+    }
+
+    /// The cards to play in place of any wildcards to make the strongest
+    /// hand, along with the type of that hand.
+    ///
+    /// No hand type looks past the two largest groups of cards, and adding
+    /// to a group never makes a hand weaker, so the only choice that
+    /// matters is how to split the wildcards between those two groups.
+    /// When the hand has fewer than two groups, wildcards can make up
+    /// groups of their own from cards that aren't in it.
+    fn best_substitution(&self, cards: &[Card]) -> (Vec<Card>, HandType) {
+        let wild = cards.iter().filter(|c| self.is_wild(c)).count();
+        let counter: Counter<Card> = cards.iter().filter(|c| !self.is_wild(c)).copied().collect();
+        let mut groups: Vec<Card> = counter.keys().copied().collect();
+        groups.sort_by_key(|c| Reverse((counter[c], self.card_rank(c))));
+        let fresh = self
+            .card_order
+            .iter()
+            .rev()
+            .filter(|c| !self.is_wild(c) && !counter.contains_key(c))
+            .copied();
+        let targets: Vec<Card> = groups.into_iter().chain(fresh).take(2).collect();
+        if wild == 0 || targets.is_empty() {
+            return (cards.to_vec(), self.plain_hand_type(cards));
+        }
+
+        // Try putting more wildcards in the first group before fewer.
+        (0..=wild)
+            .rev()
+            .map(|to_first| {
+                let mut wild_seen = 0;
+                let played: Vec<Card> = cards
+                    .iter()
+                    .map(|&card| {
+                        if !self.is_wild(&card) {
+                            return card;
+                        }
+                        wild_seen += 1;
+                        if wild_seen <= to_first {
+                            targets[0]
+                        } else {
+                            *targets.last().unwrap()
+                        }
+                    })
+                    .collect();
+                let hand_type = self.plain_hand_type(&played);
+                (played, hand_type)
+            })
+            .reduce(|best, next| {
+                if self.type_rank(&next.1) > self.type_rank(&best.1) {
+                    next
                 } else {
-                    // One pair and three single cards
-                    HandType::OnePair
+                    best
                 }
-            }
-            1 => HandType::HighCard,
-            _ => unreachable!("Minimum count is 1, maximum count is 5"),
+            })
+            .unwrap()
+    }
+
+    /// Pack a hand's type and the ranks of its cards into one integer, with
+    /// four bits per card below the type, so that comparing two strengths
+    /// compares the types first and then the cards in order.
+    fn pack_strength(&self, hand_type: HandType, cards: &[Card]) -> u64 {
+        cards
+            .iter()
+            .fold(self.type_rank(&hand_type) as u64, |key, card| {
+                key << 4 | self.card_rank(card) as u64
+            })
+    }
+
+    fn score<'a>(&self, hand: &'a Hand) -> ScoredHand<'a> {
+        let (played, hand_type) = self.best_substitution(&hand.cards);
+        ScoredHand {
+            strength: self.pack_strength(hand_type, &hand.cards),
+            played,
+            hand_type,
+            hand,
         }
     }
-}
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> Ordering {
-        let type_cmp = self.hand_type().cmp(&other.hand_type());
+    /// Compare two hands directly, working out each hand's type every time.
+    /// This is the slow path that the sort benchmark measures packed
+    /// strength against.
+    #[cfg(test)]
+    fn compare(&self, a: &Hand, b: &Hand) -> std::cmp::Ordering {
+        let type_cmp = self
+            .type_rank(&a.hand_type(self))
+            .cmp(&self.type_rank(&b.hand_type(self)));
         if type_cmp.is_eq() {
-            // Use the underlying card values to break ties.
-            // For part one, the effective cards == the underlying cards.
-            // For part two, the effective cards may be stronger than
-            // the underlying cards (due to the presence of jokers).
-            self.underlying_cards.cmp(&other.underlying_cards)
+            // Use the cards as written to break ties, so wildcards are
+            // ranked as themselves rather than what they stand in for.
+            let ranks = |hand: &Hand| hand.cards.iter().map(|c| self.card_rank(c)).collect_vec();
+            ranks(a).cmp(&ranks(b))
         } else {
             type_cmp
         }
     }
 }
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
 enum Card {
-    Joker,
     Number(u32),
     Jack,
     Queen,
@@ -146,19 +367,6 @@ enum Card {
     Ace,
 }
 
-impl Card {
-    fn jack_to_joker(self) -> Self {
-        match self {
-            Card::Jack => Card::Joker,
-            _ => self,
-        }
-    }
-
-    fn is_joker(&self) -> bool {
-        self == &Card::Joker
-    }
-}
-
 impl Hash for Card {
     fn hash<H: Hasher>(&self, state: &mut H) {
         let n = match *self {
@@ -167,12 +375,26 @@ impl Hash for Card {
             Card::Queen => 12,
             Card::Jack => 11,
             Card::Number(n) => n,
-            Card::Joker => 1,
         };
         state.write_u32(n);
     }
 }
 
+impl Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Card::*;
+        let c = match self {
+            Ace => 'A',
+            King => 'K',
+            Queen => 'Q',
+            Jack => 'J',
+            Number(10) => 'T',
+            Number(n) => char::from_digit(*n, 10).unwrap(),
+        };
+        write!(f, "{c}")
+    }
+}
+
 impl TryFrom<char> for Card {
     type Error = &'static str;
 
@@ -190,7 +412,7 @@ impl TryFrom<char> for Card {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum HandType {
     HighCard,
     OnePair,
@@ -201,9 +423,53 @@ enum HandType {
     FiveOfAKind,
 }
 
+impl HandType {
+    /// The hand type with this name, as shown in the report.
+    fn from_name(name: &str) -> Option<HandType> {
+        use HandType::*;
+        let hand_type = match name {
+            "HighCard" => HighCard,
+            "OnePair" => OnePair,
+            "TwoPair" => TwoPair,
+            "ThreeOfAKind" => ThreeOfAKind,
+            "FullHouse" => FullHouse,
+            "FourOfAKind" => FourOfAKind,
+            "FiveOfAKind" => FiveOfAKind,
+            _ => return None,
+        };
+        Some(hand_type)
+    }
+
+    /// The sizes of the groups of matching cards this type needs.
+    fn pattern(&self) -> &'static [usize] {
+        match self {
+            HandType::HighCard => &[],
+            HandType::OnePair => &[2],
+            HandType::TwoPair => &[2, 2],
+            HandType::ThreeOfAKind => &[3],
+            HandType::FullHouse => &[3, 2],
+            HandType::FourOfAKind => &[4],
+            HandType::FiveOfAKind => &[5],
+        }
+    }
+
+    /// Whether a hand with these group sizes, largest first, has at least
+    /// the groups this type needs. Four of a kind satisfies a pair, for
+    /// instance, but not a full house.
+    fn satisfied_by(&self, counts: &[usize]) -> bool {
+        let pattern = self.pattern();
+        pattern.len() <= counts.len() && pattern.iter().zip(counts).all(|(p, c)| c >= p)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::day_07::{parse_input, part_one, part_two, Hand, HandType};
+    use std::time::Instant;
+
+    use crate::day_07::{
+        parse_hands, parse_input, part_one, part_two, rank_hands, ranking_report, winnings, Card,
+        Hand, HandType, ParseError, Rules,
+    };
 
     const TEST_INPUT: &str = "\
         32T3K 765\n\
@@ -215,73 +481,321 @@ mod test {
     #[test]
     fn day7_test_parse() {
         use super::Card::*;
-        let hands = parse_input(TEST_INPUT);
+        let hands = parse_input(TEST_INPUT).unwrap();
         let expected_hands = vec![
-            Hand::new([Number(3), Number(2), Number(10), Number(3), King], 765),
-            Hand::new([Number(10), Number(5), Number(5), Jack, Number(5)], 684),
-            Hand::new([King, King, Number(6), Number(7), Number(7)], 28),
-            Hand::new([King, Number(10), Jack, Jack, Number(10)], 220),
-            Hand::new([Queen, Queen, Queen, Jack, Ace], 483),
+            Hand::new(vec![Number(3), Number(2), Number(10), Number(3), King], 765),
+            Hand::new(vec![Number(10), Number(5), Number(5), Jack, Number(5)], 684),
+            Hand::new(vec![King, King, Number(6), Number(7), Number(7)], 28),
+            Hand::new(vec![King, Number(10), Jack, Jack, Number(10)], 220),
+            Hand::new(vec![Queen, Queen, Queen, Jack, Ace], 483),
         ];
 
-        for (got, expected) in hands.into_iter().zip(expected_hands.into_iter()) {
+        for (got, expected) in hands.into_iter().zip(expected_hands) {
             assert_eq!(got, expected);
         }
     }
 
     #[test]
     fn day7_test_hand_type() {
-        let hands = parse_input(TEST_INPUT);
-        assert_eq!(hands[0].hand_type(), HandType::OnePair);
-        assert_eq!(hands[1].hand_type(), HandType::ThreeOfAKind);
-        assert_eq!(hands[2].hand_type(), HandType::TwoPair);
-        assert_eq!(hands[3].hand_type(), HandType::TwoPair);
-        assert_eq!(hands[4].hand_type(), HandType::ThreeOfAKind);
-
-        let hands = parse_input("23232 1\nA9AAA 2");
-        assert_eq!(hands[0].hand_type(), HandType::FullHouse);
-        assert_eq!(hands[1].hand_type(), HandType::FourOfAKind);
+        let rules = Rules::standard();
+        let hands = parse_input(TEST_INPUT).unwrap();
+        assert_eq!(hands[0].hand_type(&rules), HandType::OnePair);
+        assert_eq!(hands[1].hand_type(&rules), HandType::ThreeOfAKind);
+        assert_eq!(hands[2].hand_type(&rules), HandType::TwoPair);
+        assert_eq!(hands[3].hand_type(&rules), HandType::TwoPair);
+        assert_eq!(hands[4].hand_type(&rules), HandType::ThreeOfAKind);
+
+        let hands = parse_input("23232 1\nA9AAA 2").unwrap();
+        assert_eq!(hands[0].hand_type(&rules), HandType::FullHouse);
+        assert_eq!(hands[1].hand_type(&rules), HandType::FourOfAKind);
     }
 
     #[test]
     fn day7_part1_winnings() {
-        let hands = parse_input(TEST_INPUT);
+        let hands = parse_input(TEST_INPUT).unwrap();
         assert_eq!(part_one(hands), 6440);
     }
 
     #[test]
-    fn day7_jack_to_joker() {
+    fn day7_joker_rules() {
         use super::Card::*;
 
-        let q = Queen.jack_to_joker();
-        assert_eq!(q, Queen);
-
-        let j = Jack.jack_to_joker();
-        assert_eq!(j, Joker);
+        let rules = Rules::jokers();
+        assert!(rules.is_wild(&Jack));
+        assert!(!rules.is_wild(&Queen));
+        assert_eq!(rules.card_rank(&Jack), 0);
+        assert!(rules.card_rank(&Number(2)) > rules.card_rank(&Jack));
     }
 
     #[test]
     fn day7_test_input_parse() {
-        let hands: Vec<Hand> = parse_input(TEST_INPUT)
-            .into_iter()
-            .map(|h| h.jack_to_joker())
-            .collect();
-        assert_eq!(hands[0].hand_type(), HandType::OnePair, "32T3K");
-        assert_eq!(hands[1].hand_type(), HandType::FourOfAKind, "T55J5");
-        assert_eq!(hands[2].hand_type(), HandType::TwoPair, "KK677");
-        assert_eq!(hands[3].hand_type(), HandType::FourOfAKind, "KTJJT");
-        assert_eq!(hands[4].hand_type(), HandType::FourOfAKind, "QQQJA");
+        let rules = Rules::jokers();
+        let hands = parse_input(TEST_INPUT).unwrap();
+        assert_eq!(hands[0].hand_type(&rules), HandType::OnePair, "32T3K");
+        assert_eq!(hands[1].hand_type(&rules), HandType::FourOfAKind, "T55J5");
+        assert_eq!(hands[2].hand_type(&rules), HandType::TwoPair, "KK677");
+        assert_eq!(hands[3].hand_type(&rules), HandType::FourOfAKind, "KTJJT");
+        assert_eq!(hands[4].hand_type(&rules), HandType::FourOfAKind, "QQQJA");
+    }
+
+    #[test]
+    fn day7_joker_substitution() {
+        use super::Card::*;
+
+        let rules = Rules::jokers();
+        let hands = parse_input("KTJJT 1\nJJJJJ 2").unwrap();
+        let (effective, hand_type) = rules.best_substitution(&hands[0].cards);
+        assert_eq!(
+            effective,
+            [King, Number(10), Number(10), Number(10), Number(10)]
+        );
+        assert_eq!(hand_type, HandType::FourOfAKind);
+        let (effective, hand_type) = rules.best_substitution(&hands[1].cards);
+        assert_eq!(effective, [Ace; 5]);
+        assert_eq!(hand_type, HandType::FiveOfAKind);
+    }
+
+    #[test]
+    fn day7_twos_wild() {
+        use super::Card::*;
+
+        let rules = Rules::standard().with_wildcard(Number(2));
+        let hands = parse_input("2K2KA 1\nJJ22Q 2\n32T3K 3").unwrap();
+        assert_eq!(hands[0].hand_type(&rules), HandType::FourOfAKind);
+        assert_eq!(hands[1].hand_type(&rules), HandType::FourOfAKind);
+        assert_eq!(hands[2].hand_type(&rules), HandType::ThreeOfAKind);
+        // Jacks are still jacks, and twos are the weakest card, so the
+        // ranks are 32T3K, 2K2KA, JJ22Q.
+        assert_eq!(rules.card_rank(&Number(2)), 0);
+        assert_eq!(winnings(hands, &rules), 11);
+    }
+
+    #[test]
+    fn day7_no_full_house() {
+        let rules = Rules::standard().without_hand_type(HandType::FullHouse);
+        let hands = parse_input("23232 1\nA9AAA 2").unwrap();
+        assert_eq!(hands[0].hand_type(&rules), HandType::ThreeOfAKind);
+        assert_eq!(hands[1].hand_type(&rules), HandType::FourOfAKind);
+
+        // Without four of a kind, a joker is better spent on a full house.
+        let rules = Rules::jokers().without_hand_type(HandType::FourOfAKind);
+        let hands = parse_input("AAAJK 1\nAAAAK 2").unwrap();
+        assert_eq!(hands[0].hand_type(&rules), HandType::FullHouse);
+        assert_eq!(hands[1].hand_type(&rules), HandType::ThreeOfAKind);
     }
 
     #[test]
     fn day7_part2_winnings() {
-        let hands = parse_input(TEST_INPUT);
+        let hands = parse_input(TEST_INPUT).unwrap();
         assert_eq!(part_two(hands), 5905);
     }
 
     #[test]
     fn day7_real_part1() {
-        let hands = parse_input(super::INPUT);
+        let hands = parse_input(super::INPUT).unwrap();
         assert_eq!(part_one(hands), 248105065);
     }
+
+    #[test]
+    fn day7_real_part2() {
+        let hands = parse_input(super::INPUT).unwrap();
+        assert_eq!(part_two(hands), 249515436);
+    }
+
+    #[test]
+    fn day7_strength_matches_compare() {
+        let hands = parse_input(super::INPUT).unwrap();
+        for rules in [Rules::standard(), Rules::jokers()] {
+            let mut compared = hands.clone();
+            compared.sort_by(|a, b| rules.compare(a, b));
+            let ranked: Vec<&Hand> = rank_hands(&hands, &rules)
+                .into_iter()
+                .map(|s| s.hand)
+                .collect();
+            assert!(ranked.into_iter().eq(compared.iter()));
+        }
+    }
+
+    #[test]
+    fn day7_strength_packing() {
+        let rules = Rules::standard();
+        let hands = parse_input("23456 1\n22345 2\nAAAAA 3").unwrap();
+        // High card is type 0, and a two ranks 0, up to 4 for a six.
+        assert_eq!(rules.score(&hands[0]).strength, 0x01234);
+        assert_eq!(rules.score(&hands[1]).strength, 0x100123);
+        assert_eq!(rules.score(&hands[2]).strength, 0x6CCCCC);
+    }
+
+    #[test]
+    fn day7_ranking_report() {
+        let hands = parse_input(TEST_INPUT).unwrap();
+        let report = ranking_report(&hands, &Rules::jokers());
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[1], "1\t32T3K\t32T3K\tOnePair     \t765\t765");
+        assert_eq!(lines[2], "2\tKK677\tKK677\tTwoPair     \t28\t56");
+        assert_eq!(lines[5], "5\tKTJJT\tKTTTT\tFourOfAKind \t220\t1100");
+        assert_eq!(lines[6], "Total winnings: 5905");
+    }
+
+    #[test]
+    fn day7_report_without_hand_types() {
+        let without = |names: &[&str]| names.iter().map(|&n| n.to_owned()).collect::<Vec<_>>();
+        let report = super::report(false, &without(&["FullHouse", "FiveOfAKind"]));
+        assert!(!report.contains("FullHouse"));
+        assert!(!report.contains("FiveOfAKind"));
+        let rules = Rules::standard()
+            .without_hand_type(HandType::FullHouse)
+            .without_hand_type(HandType::FiveOfAKind);
+        let expected = super::winnings(parse_input(super::INPUT).unwrap(), &rules);
+        assert!(report.ends_with(&format!("Total winnings: {expected}")));
+        assert_eq!(
+            super::report(false, &without(&["Flush"])),
+            "Unknown hand type \"Flush\"."
+        );
+        assert_eq!(
+            super::report(true, &without(&["HighCard"])),
+            "HighCard can't be removed."
+        );
+    }
+
+    #[test]
+    fn day7_parse_errors() {
+        let error = |line, reason: &str| ParseError {
+            line: Some(line),
+            reason: reason.to_owned(),
+        };
+        assert_eq!(
+            parse_input("32T3K 765\n32X3K 684"),
+            Err(error(2, "Unknown card 'X' at position 3"))
+        );
+        assert_eq!(
+            parse_input("32T3K765"),
+            Err(error(1, "No space between cards and bid in \"32T3K765\""))
+        );
+        assert_eq!(
+            parse_input("32T3 765"),
+            Err(error(1, "Expected 5 cards but found 4"))
+        );
+        assert_eq!(
+            parse_input("32T3K 7x5"),
+            Err(error(1, "Bid \"7x5\" is not a number"))
+        );
+        assert_eq!(
+            parse_input("32T3K 765\n\nKK677 28\n").map(|hands| hands.len()),
+            Ok(2)
+        );
+        assert_eq!(
+            error(3, "Expected 5 cards but found 4").to_string(),
+            "line 3: Expected 5 cards but found 4"
+        );
+    }
+
+    #[test]
+    fn day7_unsupported_hand_lengths() {
+        let unsupported = ParseError {
+            line: None,
+            reason: "Hands must have between 1 and 15 cards.".to_owned(),
+        };
+        assert_eq!(unsupported.to_string(), unsupported.reason);
+        assert_eq!(parse_hands("A 1", 0), Err(unsupported));
+        let sixteen = format!("{} 1", "A".repeat(16));
+        assert!(parse_hands(&sixteen, 16).is_err_and(|e| e.line.is_none()));
+    }
+
+    #[test]
+    fn day7_other_hand_lengths() {
+        let rules = Rules::jokers();
+        let hands = parse_hands("AAAJKKK 1\n2345678 2\n23 3", 7);
+        assert!(hands.is_err());
+
+        let hands = parse_hands("AAAJKKK 1\n2345678 2", 7).unwrap();
+        assert_eq!(hands[0].hand_type(&rules), HandType::FourOfAKind);
+        assert_eq!(hands[1].hand_type(&rules), HandType::HighCard);
+        assert_eq!(winnings(hands, &rules), 4);
+
+        let hands = parse_hands("A 1\nJ 2\n2 3", 1).unwrap();
+        assert_eq!(hands[1].hand_type(&rules), HandType::HighCard);
+        // Ranked J, 2, A.
+        assert_eq!(winnings(hands, &rules), 11);
+    }
+
+    #[test]
+    fn day7_many_wildcards() {
+        let rules = Rules::jokers();
+        let hands = parse_hands(&format!("{} 1\n{}A 2", "J".repeat(15), "J".repeat(14)), 15);
+        for hand in hands.unwrap() {
+            assert_eq!(hand.hand_type(&rules), HandType::FiveOfAKind);
+        }
+    }
+
+    #[test]
+    fn day7_substitution_matches_brute_force() {
+        use itertools::Itertools;
+        use Card::*;
+        let variants = [
+            Rules::jokers(),
+            Rules::jokers().without_hand_type(HandType::FourOfAKind),
+            Rules::jokers().without_hand_type(HandType::FullHouse),
+        ];
+        for rules in variants {
+            let everything: Vec<Card> = rules
+                .card_order
+                .iter()
+                .filter(|c| !rules.is_wild(c))
+                .copied()
+                .collect();
+            for cards in std::iter::repeat_n([Number(2), Number(3), King, Ace, Jack], 4)
+                .multi_cartesian_product()
+            {
+                // Every card the wildcards could possibly stand in for.
+                let brute_force = cards
+                    .iter()
+                    .map(|&c| {
+                        if rules.is_wild(&c) {
+                            everything.clone()
+                        } else {
+                            vec![c]
+                        }
+                    })
+                    .multi_cartesian_product()
+                    .map(|played| rules.type_rank(&rules.plain_hand_type(&played)))
+                    .max()
+                    .unwrap();
+                let (played, hand_type) = rules.best_substitution(&cards);
+                assert_eq!(rules.type_rank(&hand_type), brute_force, "{cards:?}");
+                assert_eq!(rules.plain_hand_type(&played), hand_type);
+            }
+        }
+    }
+
+    /// Run with `cargo test --release day7_bench -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn day7_bench_sort() {
+        let hands = parse_input(super::INPUT).unwrap();
+        let rules = Rules::jokers();
+        let runs = 20;
+
+        let start = Instant::now();
+        for _ in 0..runs {
+            let mut sorted = hands.clone();
+            sorted.sort_by(|a, b| rules.compare(a, b));
+        }
+        let compared = start.elapsed() / runs;
+
+        let start = Instant::now();
+        for _ in 0..runs {
+            rank_hands(&hands, &rules);
+        }
+        let keyed = start.elapsed() / runs;
+
+        // Timings depend on the machine, so report them rather than assert.
+        println!("Sort by comparison:\t{compared:?}");
+        println!("Sort by packed key:\t{keyed:?}");
+        println!(
+            "Speed-up:\t\t{:.1}x",
+            compared.as_secs_f64() / keyed.as_secs_f64()
+        );
+    }
 }
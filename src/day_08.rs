@@ -1,20 +1,54 @@
-use std::collections::HashMap;
+use itertools::Itertools;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
 
-use crate::utils::{self, lcm};
+use crate::utils::{self, crt};
 
 const INPUT: &str = include_str!("input/2023_08.txt");
 
 pub fn run() -> String {
     let map = parse_input(INPUT);
-    utils::both(map.steps_to_zzz(), map.steps_to_all_z())
+    let show = |result: Result<usize, &str>| result.map_or_else(str::to_owned, |s| s.to_string());
+    utils::both(show(map.steps_to_zzz()), show(map.steps_to_all_z()))
 }
 
-#[derive(Debug)]
+/// The node network as a Graphviz DOT graph, optionally with the path
+/// each ghost takes to its first goal drawn over the top.
+pub fn dot(paths: bool) -> String {
+    parse_input(INPUT)
+        .compile()
+        .to_dot(|n| n.ends_with('A'), |n| n.ends_with('Z'), paths)
+}
+
+/// Where each ghost is after `steps` steps, found by jumping through the
+/// binary lifting table rather than walking every step.
+pub fn walk(steps: usize) -> String {
+    parse_input(INPUT)
+        .compile()
+        .with_lifting()
+        .positions_after(|n| n.ends_with('A'), steps)
+        .into_iter()
+        .map(|(start, end)| format!("{start} -> {end}"))
+        .join("\n")
+}
+
+#[derive(Debug, Clone, Copy)]
 enum Direction {
     Left,
     Right,
 }
 
+impl Direction {
+    fn label(self) -> char {
+        match self {
+            Self::Left => 'L',
+            Self::Right => 'R',
+        }
+    }
+}
+
 impl From<char> for Direction {
     fn from(value: char) -> Self {
         match value {
@@ -32,60 +66,345 @@ struct Map<'a> {
 }
 
 impl<'a> Map<'a> {
-    fn steps_to_zzz(&self) -> usize {
-        let mut current = "AAA";
-        for (count, direction) in self.order.iter().cycle().enumerate() {
-            if current == "ZZZ" {
-                return count;
+    fn steps_to_zzz(&self) -> Result<usize, &'static str> {
+        self.steps_from_to(|n| n == "AAA", |n| n == "ZZZ")
+    }
+
+    /// Steps until every ghost is on a node ending in Z at the same time.
+    fn steps_to_all_z(&self) -> Result<usize, &'static str> {
+        self.steps_from_to(|n| n.ends_with('A'), |n| n.ends_with('Z'))
+    }
+
+    /// Steps until a ghost starting at each node matching `is_start` is on
+    /// a node matching `is_goal`, all at the same time.
+    fn steps_from_to(
+        &self,
+        is_start: impl Fn(&str) -> bool,
+        is_goal: impl Fn(&str) -> bool,
+    ) -> Result<usize, &'static str> {
+        self.compile().steps_from_to(is_start, is_goal)
+    }
+
+    /// Compile the network so nodes are referred to by index.
+    fn compile(&self) -> Network<'a> {
+        let names: Vec<&str> = self.map.keys().copied().sorted().collect();
+        let indices: HashMap<&str, usize> =
+            names.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+        let edges: Vec<[usize; 2]> = names
+            .iter()
+            .map(|name| {
+                let (left, right) = self.map[name];
+                [indices[left], indices[right]]
+            })
+            .collect();
+
+        // Run every node through the whole set of instructions at once.
+        let mut full_pass: Vec<usize> = (0..names.len()).collect();
+        for &direction in &self.order {
+            for node in full_pass.iter_mut() {
+                *node = edges[*node][direction as usize];
             }
-            let (left, right) = self.map.get(current).unwrap();
-            current = match direction {
-                Direction::Left => left,
-                Direction::Right => right,
-            };
         }
-        unreachable!();
+
+        Network {
+            names,
+            edges,
+            order: self.order.clone(),
+            full_pass,
+            lifts: Vec::new(),
+        }
+    }
+}
+
+/// The node network with each node replaced by an index, so that walking
+/// it is a matter of array lookups rather than hashing node names.
+#[derive(Debug)]
+struct Network<'a> {
+    /// Node names, sorted, so a node's index is its position here.
+    names: Vec<&'a str>,
+    /// Left and right neighbours of each node.
+    edges: Vec<[usize; 2]>,
+    order: Vec<Direction>,
+    /// Node reached from each node after one full pass of the instructions.
+    full_pass: Vec<usize>,
+    /// Binary lifting table, where `lifts[k][n]` is the node reached from
+    /// node n after 2^k full passes of the instructions. Empty unless
+    /// built with `with_lifting`.
+    lifts: Vec<Vec<usize>>,
+}
+
+impl<'a> Network<'a> {
+    #[cfg(test)]
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.names.binary_search(&name).ok()
+    }
+
+    /// Build the binary lifting table, with enough levels to cover any
+    /// number of steps that fits in a usize.
+    fn with_lifting(mut self) -> Self {
+        if self.order.is_empty() {
+            return self;
+        }
+        // One level for each bit of the largest number of passes.
+        let max_passes = usize::MAX / self.order.len();
+        let levels = max_passes.ilog2() + 1;
+        let mut lifts = vec![self.full_pass.clone()];
+        for _ in 1..levels {
+            let previous = lifts.last().unwrap();
+            let next = previous.iter().map(|&n| previous[n]).collect();
+            lifts.push(next);
+        }
+        self.lifts = lifts;
+        self
+    }
+
+    /// The node reached after walking a number of steps from a start node,
+    /// assuming the walk begins at the start of the instructions.
+    ///
+    /// Whole passes of the instructions are taken as single jumps, or as
+    /// power-of-two jumps if the lifting table has been built, and only the
+    /// remainder is simulated step by step. With no instructions, there's
+    /// nowhere to go.
+    fn walk(&self, start: usize, steps: usize) -> usize {
+        if self.order.is_empty() {
+            return start;
+        }
+        let passes = steps / self.order.len();
+        let remainder = steps % self.order.len();
+        let mut node = start;
+        if self.lifts.is_empty() {
+            for _ in 0..passes {
+                node = self.full_pass[node];
+            }
+        } else {
+            for (level, lift) in self.lifts.iter().enumerate() {
+                if passes & (1 << level) != 0 {
+                    node = lift[node];
+                }
+            }
+        }
+        for &direction in &self.order[..remainder] {
+            node = self.edges[node][direction as usize];
+        }
+        node
+    }
+
+    /// Each start node matching `is_start`, and the node a ghost starting
+    /// there is on after `steps` steps.
+    fn positions_after(
+        &self,
+        is_start: impl Fn(&str) -> bool,
+        steps: usize,
+    ) -> Vec<(&'a str, &'a str)> {
+        self.names
+            .iter()
+            .positions(|n| is_start(n))
+            .map(|start| (self.names[start], self.names[self.walk(start, steps)]))
+            .collect()
     }
 
-    #[allow(dead_code)]
-    fn steps_to_all_z(&self) -> usize {
-        let mut current_nodes: Vec<&str> = self
-            .map
-            .keys()
-            .filter(|k| k.ends_with('A'))
-            .cloned()
+    /// Steps until a ghost starting at each node matching `is_start` is on
+    /// a node matching `is_goal`, all at the same time.
+    ///
+    /// Each ghost's walk is followed until it repeats, so a goal that can
+    /// never be reached is reported as an error rather than walked forever.
+    fn steps_from_to(
+        &self,
+        is_start: impl Fn(&str) -> bool,
+        is_goal: impl Fn(&str) -> bool,
+    ) -> Result<usize, &'static str> {
+        if self.order.is_empty() {
+            return Err("There are no instructions to follow.");
+        }
+        let goals: Vec<bool> = self.names.iter().map(|n| is_goal(n)).collect();
+        let cycles: Vec<GhostCycle> = self
+            .names
+            .iter()
+            .positions(|n| is_start(n))
+            .map(|start| self.ghost_cycle(start, &goals))
             .collect();
-        let mut steps_to_end: Vec<usize> = vec![0; current_nodes.len()];
-        for (count, direction) in self.order.iter().cycle().enumerate() {
-            let mut nodes: Vec<&str> = Vec::with_capacity(current_nodes.len());
-            // eprintln!("{:?}\t{:?}\t{:?}", count, current_nodes, steps_to_end);
-            for (idx, n) in current_nodes.iter().enumerate() {
-                let next = if n.ends_with('Z') {
-                    if steps_to_end[idx] == 0 {
-                        steps_to_end[idx] = count;
-                    }
-                    n
-                } else {
-                    let (left, right) = self.map.get(n).unwrap();
-                    match direction {
-                        Direction::Left => left,
-                        Direction::Right => right,
-                    }
+        if cycles.is_empty() {
+            return Err("No start nodes found.");
+        }
+        if cycles.iter().any(GhostCycle::never_hits) {
+            return Err("Goal is unreachable from a start node.");
+        }
+        combine_cycles(&cycles)?.ok_or("Ghosts never reach a goal at the same time.")
+    }
+
+    /// Walk a ghost from its start node until it returns to a state it has
+    /// been in before, where a state is its node and its position in the
+    /// instructions. From then on the walk repeats exactly.
+    fn ghost_cycle(&self, start: usize, is_goal: &[bool]) -> GhostCycle {
+        let len = self.order.len();
+        // Step at which each state was first seen, indexed by node * len + idx.
+        let mut seen: Vec<Option<usize>> = vec![None; self.names.len() * len];
+        let mut hits: Vec<usize> = Vec::new();
+        let mut current = start;
+        let instructions = self.order.iter().enumerate().cycle();
+        for (step, (idx, &direction)) in instructions.enumerate() {
+            if let Some(offset) = seen[current * len + idx] {
+                let (lead_in_hits, cycle_hits) = hits.into_iter().partition(|&h| h < offset);
+                return GhostCycle {
+                    offset,
+                    length: step - offset,
+                    lead_in_hits,
+                    cycle_hits,
                 };
-                nodes.push(next);
             }
-            // Found a count for each node, so break.
-            if steps_to_end.iter().all(|&n| n != 0) {
-                break;
+            seen[current * len + idx] = Some(step);
+            if is_goal[current] {
+                hits.push(step);
             }
-            // Safety valve.
-            if count > 1_000_000 {
+            current = self.edges[current][direction as usize];
+        }
+        unreachable!();
+    }
+
+    /// The edges a ghost takes from its start node until it first reaches
+    /// a goal, or until its walk starts to repeat if it never does.
+    fn ghost_path(&self, start: usize, goals: &[bool]) -> Vec<(usize, Direction)> {
+        let mut seen: HashSet<(usize, usize)> = HashSet::new();
+        let mut path = Vec::new();
+        let mut current = start;
+        for (idx, &direction) in self.order.iter().enumerate().cycle() {
+            if goals[current] || !seen.insert((current, idx)) {
                 break;
             }
-            current_nodes = nodes;
+            path.push((current, direction));
+            current = self.edges[current][direction as usize];
         }
-        lcm(&steps_to_end)
+        path
+    }
+
+    /// Render the network as a Graphviz DOT graph, with edges labelled by
+    /// direction and start and goal nodes filled in.
+    ///
+    /// With `paths`, each ghost's path to its first goal is overlaid as a
+    /// second set of coloured edges.
+    fn to_dot(
+        &self,
+        is_start: impl Fn(&str) -> bool,
+        is_goal: impl Fn(&str) -> bool,
+        paths: bool,
+    ) -> String {
+        const PATH_COLOURS: [&str; 6] =
+            ["blue", "darkorange", "purple", "darkgreen", "red", "brown"];
+
+        let goals: Vec<bool> = self.names.iter().map(|n| is_goal(n)).collect();
+        let mut out = String::from("digraph network {\n");
+        for (idx, name) in self.names.iter().enumerate() {
+            if is_start(name) {
+                writeln!(out, "    \"{name}\" [style=filled, fillcolor=palegreen];").unwrap();
+            } else if goals[idx] {
+                writeln!(out, "    \"{name}\" [style=filled, fillcolor=lightcoral];").unwrap();
+            }
+        }
+        for (idx, name) in self.names.iter().enumerate() {
+            for direction in [Direction::Left, Direction::Right] {
+                let to = self.names[self.edges[idx][direction as usize]];
+                let label = direction.label();
+                writeln!(out, "    \"{name}\" -> \"{to}\" [label=\"{label}\"];").unwrap();
+            }
+        }
+        if paths {
+            let starts = self.names.iter().positions(|n| is_start(n));
+            for (start, colour) in starts.zip(PATH_COLOURS.iter().cycle()) {
+                // Only draw each edge once per ghost, even if it's taken often.
+                let edges: Vec<(usize, Direction)> = self
+                    .ghost_path(start, &goals)
+                    .into_iter()
+                    .unique_by(|&(node, direction)| (node, direction as usize))
+                    .collect();
+                for (node, direction) in edges {
+                    let from = self.names[node];
+                    let to = self.names[self.edges[node][direction as usize]];
+                    let label = direction.label();
+                    writeln!(
+                        out,
+                        "    \"{from}\" -> \"{to}\" [label=\"{label}\", color={colour}, penwidth=2];"
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// How a single ghost's walk repeats.
+#[derive(Debug, PartialEq, Eq)]
+struct GhostCycle {
+    /// Step at which the repeating part of the walk begins.
+    offset: usize,
+    /// Number of steps before the walk repeats.
+    length: usize,
+    /// Steps before the cycle begins that land on a goal node.
+    lead_in_hits: Vec<usize>,
+    /// Steps in the first pass through the cycle that land on a goal node.
+    cycle_hits: Vec<usize>,
+}
+
+impl GhostCycle {
+    fn never_hits(&self) -> bool {
+        self.lead_in_hits.is_empty() && self.cycle_hits.is_empty()
+    }
+
+    fn is_hit(&self, step: usize) -> bool {
+        if step < self.offset {
+            self.lead_in_hits.contains(&step)
+        } else {
+            let first_pass = self.offset + (step - self.offset) % self.length;
+            self.cycle_hits.contains(&first_pass)
+        }
+    }
+}
+
+/// Find the first step at which every ghost is on a goal node.
+///
+/// Before every ghost has entered its cycle, the only candidates are the
+/// lead-in hits of the ghost that takes longest to get there. After that,
+/// each ghost is on a goal when the step is congruent to one of its cycle
+/// hits modulo its cycle length, so each combination of hits is solved
+/// with the Chinese Remainder Theorem and the earliest solution is taken.
+///
+/// Combinations that can never line up are skipped, but a step count too
+/// large for a usize is an error rather than a missing answer.
+fn combine_cycles(cycles: &[GhostCycle]) -> Result<Option<usize>, &'static str> {
+    let Some(latest) = cycles.iter().max_by_key(|c| c.offset) else {
+        return Ok(None);
+    };
+    if let Some(step) = latest
+        .lead_in_hits
+        .iter()
+        .copied()
+        .find(|&step| cycles.iter().all(|c| c.is_hit(step)))
+    {
+        return Ok(Some(step));
     }
+
+    let earliest = latest.offset;
+    cycles
+        .iter()
+        .map(|c| c.cycle_hits.iter().map(|&h| (h % c.length, c.length)))
+        .multi_cartesian_product()
+        .filter_map(|congruences| match crt(&congruences) {
+            Err("Congruences are inconsistent.") => None,
+            solution => Some(solution),
+        })
+        .map(|solution| {
+            let (residue, modulus) = solution?;
+            if residue >= earliest {
+                return Ok(residue);
+            }
+            (earliest - residue)
+                .div_ceil(modulus)
+                .checked_mul(modulus)
+                .and_then(|offset| offset.checked_add(residue))
+                .ok_or("Step count overflowed.")
+        })
+        .process_results(|steps| steps.min())
 }
 
 fn parse_input(test_input: &str) -> Map<'_> {
@@ -116,7 +435,7 @@ fn parse_input(test_input: &str) -> Map<'_> {
 
 #[cfg(test)]
 mod test {
-    use super::parse_input;
+    use super::{combine_cycles, parse_input, GhostCycle};
     use crate::utils::lcm;
 
     const TEST_INPUT_1: &str = "\
@@ -159,21 +478,21 @@ mod test {
     fn day8_part_one_test_reach_zzz_lr() {
         let map = parse_input(TEST_INPUT_1);
         let steps = map.steps_to_zzz();
-        assert_eq!(steps, 2);
+        assert_eq!(steps, Ok(2));
     }
 
     #[test]
     fn day8_part_one_test_reach_zzz_llr() {
         let map = parse_input(TEST_INPUT_2);
         let steps = map.steps_to_zzz();
-        assert_eq!(steps, 6);
+        assert_eq!(steps, Ok(6));
     }
 
     #[test]
     fn day8_part_one_real_input() {
         let map = parse_input(super::INPUT);
         let steps = map.steps_to_zzz();
-        assert_eq!(steps, 22199);
+        assert_eq!(steps, Ok(22199));
     }
 
     #[test]
@@ -186,21 +505,321 @@ mod test {
     #[test]
     fn day8_test_part_two() {
         let map = parse_input(TEST_INPUT_3);
-        let expected = 6;
+        let expected = Ok(6);
         let result = map.steps_to_all_z();
         assert_eq!(result, expected);
     }
 
     #[test]
     fn day8_test_lcm() {
-        assert_eq!(lcm(&[1, 2, 3, 4, 5]), 60);
+        assert_eq!(lcm(&[1, 2, 3, 4, 5]), Some(60));
     }
 
     #[test]
     fn day8_real_part_two() {
         let map = parse_input(super::INPUT);
-        let expected: usize = 13334102464297;
+        let expected = Ok(13334102464297);
         let result = map.steps_to_all_z();
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn day8_ghost_cycle() {
+        let network = parse_input(TEST_INPUT_3).compile();
+        let is_goal: Vec<bool> = network.names.iter().map(|n| n.ends_with('Z')).collect();
+        let ghost_cycle = |name| network.ghost_cycle(network.index_of(name).unwrap(), &is_goal);
+        assert_eq!(
+            ghost_cycle("11A"),
+            GhostCycle {
+                offset: 1,
+                length: 2,
+                lead_in_hits: vec![],
+                cycle_hits: vec![2],
+            }
+        );
+        assert_eq!(
+            ghost_cycle("22A"),
+            GhostCycle {
+                offset: 1,
+                length: 6,
+                lead_in_hits: vec![],
+                cycle_hits: vec![3, 6],
+            }
+        );
+    }
+
+    #[test]
+    fn day8_combine_cycles_with_offsets() {
+        // Hits at 3, 8, 13, … and at 4, 11, 18, …, which first meet at 18
+        // rather than the LCM of the cycle lengths.
+        let cycles = [
+            GhostCycle {
+                offset: 0,
+                length: 5,
+                lead_in_hits: vec![],
+                cycle_hits: vec![3],
+            },
+            GhostCycle {
+                offset: 2,
+                length: 7,
+                lead_in_hits: vec![],
+                cycle_hits: vec![4],
+            },
+        ];
+        assert_eq!(combine_cycles(&cycles), Ok(Some(18)));
+    }
+
+    #[test]
+    fn day8_combine_cycles_lead_in() {
+        let cycles = [
+            GhostCycle {
+                offset: 10,
+                length: 4,
+                lead_in_hits: vec![2, 5],
+                cycle_hits: vec![11],
+            },
+            GhostCycle {
+                offset: 0,
+                length: 5,
+                lead_in_hits: vec![],
+                cycle_hits: vec![0],
+            },
+        ];
+        assert_eq!(combine_cycles(&cycles), Ok(Some(5)));
+    }
+
+    #[test]
+    fn day8_combine_cycles_no_solution() {
+        // Always even steps against always odd steps.
+        let cycles = [
+            GhostCycle {
+                offset: 0,
+                length: 2,
+                lead_in_hits: vec![],
+                cycle_hits: vec![0],
+            },
+            GhostCycle {
+                offset: 0,
+                length: 4,
+                lead_in_hits: vec![],
+                cycle_hits: vec![1, 3],
+            },
+        ];
+        assert_eq!(combine_cycles(&cycles), Ok(None));
+    }
+
+    #[test]
+    fn day8_combine_cycles_overflow() {
+        // Coprime lengths whose LCM is far past usize::MAX.
+        let length = 1 << (usize::BITS - 8);
+        let cycles = [
+            GhostCycle {
+                offset: 0,
+                length,
+                lead_in_hits: vec![],
+                cycle_hits: vec![0],
+            },
+            GhostCycle {
+                offset: 0,
+                length: length - 1,
+                lead_in_hits: vec![],
+                cycle_hits: vec![1],
+            },
+        ];
+        assert_eq!(combine_cycles(&cycles), Err("Combined modulus overflowed."));
+
+        // The first hit after the lead-in is past usize::MAX.
+        let cycles = [GhostCycle {
+            offset: usize::MAX - 1,
+            length: usize::MAX / 2 + 1,
+            lead_in_hits: vec![],
+            cycle_hits: vec![0],
+        }];
+        assert_eq!(combine_cycles(&cycles), Err("Step count overflowed."));
+    }
+
+    #[test]
+    fn day8_compile() {
+        let network = parse_input(TEST_INPUT_1).compile();
+        let aaa = network.index_of("AAA").unwrap();
+        let [left, right] = network.edges[aaa];
+        assert_eq!(network.names[left], "BBB");
+        assert_eq!(network.names[right], "CCC");
+        assert_eq!(network.index_of("QQQ"), None);
+        // RL from AAA goes to CCC then ZZZ.
+        assert_eq!(network.names[network.full_pass[aaa]], "ZZZ");
+    }
+
+    #[test]
+    fn day8_walk() {
+        let map = parse_input(TEST_INPUT_2);
+        let network = map.compile();
+        let aaa = network.index_of("AAA").unwrap();
+        let zzz = network.index_of("ZZZ").unwrap();
+        assert_eq!(network.walk(aaa, 0), aaa);
+        assert_eq!(network.walk(aaa, 5), network.index_of("BBB").unwrap());
+        assert_eq!(network.walk(aaa, 6), zzz);
+        assert_eq!(network.walk(aaa, 1_000_000), zzz);
+    }
+
+    #[test]
+    fn day8_walk_with_lifting_matches_simulation() {
+        let map = parse_input(super::INPUT);
+        let network = map.compile();
+        let lifted = map.compile().with_lifting();
+        for start in ["AAA", "DVA", "JHA"].map(|n| network.index_of(n).unwrap()) {
+            let mut node = start;
+            for (steps, &direction) in map.order.iter().cycle().enumerate().take(50_000) {
+                if steps % 997 == 0 {
+                    assert_eq!(network.walk(start, steps), node);
+                    assert_eq!(lifted.walk(start, steps), node);
+                }
+                node = network.edges[node][direction as usize];
+            }
+        }
+    }
+
+    #[test]
+    fn day8_walk_real_part_two() {
+        let network = parse_input(super::INPUT).compile().with_lifting();
+        let steps = 13334102464297;
+        for name in network.names.iter().filter(|n| n.ends_with('A')) {
+            let start = network.index_of(name).unwrap();
+            let end = network.walk(start, steps);
+            assert!(network.names[end].ends_with('Z'), "{name}");
+        }
+    }
+
+    #[test]
+    fn day8_positions_after() {
+        let network = parse_input(TEST_INPUT_3).compile().with_lifting();
+        let is_start = |n: &str| n.ends_with('A');
+        assert_eq!(
+            network.positions_after(is_start, 3),
+            vec![("11A", "11B"), ("22A", "22Z")]
+        );
+        assert_eq!(
+            network.positions_after(is_start, 6),
+            vec![("11A", "11Z"), ("22A", "22Z")]
+        );
+        assert_eq!(
+            network.positions_after(is_start, 5),
+            vec![("11A", "11B"), ("22A", "22C")]
+        );
+    }
+
+    #[test]
+    fn day8_custom_start_and_goal() {
+        let map = parse_input(TEST_INPUT_1);
+        assert_eq!(map.steps_from_to(|n| n == "AAA", |n| n == "CCC"), Ok(1));
+        assert_eq!(map.steps_from_to(|n| n == "AAA", |n| n == "AAA"), Ok(0));
+        assert_eq!(
+            map.steps_from_to(|n| n == "AAA" || n == "ZZZ", |n| n.contains('Z')),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn day8_unreachable_goal() {
+        let map = parse_input(TEST_INPUT_1);
+        assert_eq!(
+            map.steps_from_to(|n| n == "AAA", |n| n == "GGG"),
+            Err("Goal is unreachable from a start node.")
+        );
+        assert_eq!(
+            map.steps_from_to(|n| n == "QQQ", |n| n == "ZZZ"),
+            Err("No start nodes found.")
+        );
+
+        let map = parse_input("L\n\nAAA = (BBB, BBB)\nBBB = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)");
+        assert_eq!(
+            map.steps_to_zzz(),
+            Err("Goal is unreachable from a start node.")
+        );
+    }
+
+    #[test]
+    fn day8_goals_never_coincide() {
+        // One ghost is on a goal every even step, the other every odd step.
+        let map = parse_input(
+            "L\n\n11A = (11Z, 11Z)\n11Z = (11A, 11A)\n22A = (22B, 22B)\n22B = (22Z, 22Z)\n22Z = (22B, 22B)",
+        );
+        assert_eq!(
+            map.steps_to_all_z(),
+            Err("Ghosts never reach a goal at the same time.")
+        );
+    }
+
+    #[test]
+    fn day8_to_dot() {
+        let network = parse_input(TEST_INPUT_2).compile();
+        let expected = "\
+digraph network {
+    \"AAA\" [style=filled, fillcolor=palegreen];
+    \"ZZZ\" [style=filled, fillcolor=lightcoral];
+    \"AAA\" -> \"BBB\" [label=\"L\"];
+    \"AAA\" -> \"BBB\" [label=\"R\"];
+    \"BBB\" -> \"AAA\" [label=\"L\"];
+    \"BBB\" -> \"ZZZ\" [label=\"R\"];
+    \"ZZZ\" -> \"ZZZ\" [label=\"L\"];
+    \"ZZZ\" -> \"ZZZ\" [label=\"R\"];
+}
+";
+        assert_eq!(
+            network.to_dot(|n| n == "AAA", |n| n == "ZZZ", false),
+            expected
+        );
+    }
+
+    #[test]
+    fn day8_to_dot_with_paths() {
+        let network = parse_input(TEST_INPUT_2).compile();
+        let dot = network.to_dot(|n| n == "AAA", |n| n == "ZZZ", true);
+        let path_edges: Vec<&str> = dot.lines().filter(|l| l.contains("penwidth")).collect();
+        // LLR: AAA -L-> BBB -L-> AAA -R-> BBB -L-> AAA -L-> BBB -R-> ZZZ
+        assert_eq!(
+            path_edges,
+            vec![
+                "    \"AAA\" -> \"BBB\" [label=\"L\", color=blue, penwidth=2];",
+                "    \"BBB\" -> \"AAA\" [label=\"L\", color=blue, penwidth=2];",
+                "    \"AAA\" -> \"BBB\" [label=\"R\", color=blue, penwidth=2];",
+                "    \"BBB\" -> \"ZZZ\" [label=\"R\", color=blue, penwidth=2];",
+            ]
+        );
+    }
+
+    #[test]
+    fn day8_ghost_path_unreachable() {
+        let network = parse_input(TEST_INPUT_1).compile();
+        let goals: Vec<bool> = network.names.iter().map(|&n| n == "GGG").collect();
+        let path = network.ghost_path(network.index_of("AAA").unwrap(), &goals);
+        // AAA -R-> CCC -L-> ZZZ, then ZZZ on both instructions before repeating.
+        assert_eq!(path.len(), 4);
+    }
+
+    #[test]
+    fn day8_single_instruction() {
+        let map = parse_input("L\n\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)");
+        assert_eq!(map.steps_to_zzz(), Ok(1));
+        let network = map.compile().with_lifting();
+        assert_eq!(network.lifts.len(), usize::BITS as usize);
+        let (aaa, zzz) = (
+            network.index_of("AAA").unwrap(),
+            network.index_of("ZZZ").unwrap(),
+        );
+        assert_eq!(network.walk(aaa, 0), aaa);
+        assert_eq!(network.walk(aaa, usize::MAX), zzz);
+    }
+
+    #[test]
+    fn day8_no_instructions() {
+        let map = parse_input("\n\nAAA = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)");
+        assert_eq!(
+            map.steps_to_zzz(),
+            Err("There are no instructions to follow.")
+        );
+        let network = map.compile().with_lifting();
+        assert!(network.lifts.is_empty());
+        assert_eq!(network.walk(0, 10), 0);
+    }
 }
@@ -1,47 +1,325 @@
+use num::{BigInt, BigRational, One, Signed, Zero};
+use std::fmt::{Display, Write};
+
 use crate::utils;
 
 const INPUT: &str = include_str!("input/2023_09.txt");
 
 pub fn run() -> String {
-    let (front, back) = predict_sum_ends(parse_input(INPUT));
-    utils::both(back, front)
+    let predictions = predict_sum_ends_checked(&parse_input(INPUT), OnInvalid::Skip).unwrap();
+    let mut out = utils::both(predictions.back, predictions.front);
+    for invalid in predictions.skipped {
+        out.push_str(&format!("\nSkipped {invalid}"));
+    }
+    out
+}
+
+/// Each history's polynomial, its value either side of the history, and
+/// its value at `at` if given.
+pub fn fit(at: Option<i64>) -> String {
+    fit_report(&parse_input(INPUT), at)
+}
+
+fn fit_report(histories: &[Vec<i32>], at: Option<i64>) -> String {
+    let mut lines = Vec::new();
+    for (idx, history) in histories.iter().enumerate() {
+        let line = idx + 1;
+        let polynomial = match Polynomial::fit(history) {
+            Ok(polynomial) => polynomial,
+            Err(reason) => {
+                lines.push(InvalidHistory { line, reason }.to_string());
+                continue;
+            }
+        };
+        let mut out = format!(
+            "line {line}: degree {}, p(x) = {polynomial}",
+            polynomial.degree()
+        );
+        let end = history.len() as i64;
+        for index in [-1, end].into_iter().chain(at) {
+            write!(out, ", p({index}) = {}", extrapolate(history, index)).unwrap();
+        }
+        let triangle = predict_ends_triangle(history)
+            .map(|(first, last)| (BigInt::from(first), BigInt::from(last)));
+        match triangle {
+            Ok(ends) if ends == (polynomial.evaluate(-1), polynomial.evaluate(end)) => {}
+            Ok(_) => out.push_str(", difference triangle disagrees"),
+            Err(reason) => write!(out, ", difference triangle failed: {reason}").unwrap(),
+        }
+        lines.push(out);
+    }
+    lines.join("\n")
 }
 
-fn predict_sum_ends(histories: Vec<Vec<i32>>) -> (i32, i32) {
-    let (fronts, backs): (Vec<i32>, Vec<i32>) = histories.iter().map(|v| predict_ends(v)).unzip();
-    (fronts.into_iter().sum(), backs.into_iter().sum())
+/// The values one step before the first and one step after the last, or
+/// why the history can't be trusted to predict them.
+fn predict_ends(history: &[i32]) -> Result<(BigInt, BigInt), &'static str> {
+    let polynomial = Polynomial::fit(history)?;
+    Ok((
+        polynomial.evaluate(-1),
+        polynomial.evaluate(history.len() as i64),
+    ))
 }
 
-fn predict_ends(history: &[i32]) -> (i32, i32) {
-    let triangle = difference_triangle(history);
-    (predict_first(&triangle), predict_last(&triangle))
+/// The values either side of the history, from the i32 difference
+/// triangle. This is the original method, which `9 fit` checks Newton's
+/// against, so it reports overflow rather than wrapping.
+fn predict_ends_triangle(history: &[i32]) -> Result<(i32, i32), &'static str> {
+    let triangle = difference_triangle(history)?;
+    Ok((predict_first(&triangle)?, predict_last(&triangle)?))
 }
 
-fn predict_first(triangle: &[Vec<i32>]) -> i32 {
+fn predict_first(triangle: &[Vec<i32>]) -> Result<i32, &'static str> {
     let first_nums: Vec<i32> = triangle.iter().map(|v| v[0]).rev().collect();
     let (initial, diffs) = first_nums.split_last().unwrap();
-    let total_diff = diffs.iter().copied().fold(0, |left, right| right - left);
-    initial - total_diff
+    let total_diff = diffs
+        .iter()
+        .try_fold(0, |left: i32, right| right.checked_sub(left))
+        .ok_or("Prediction overflows.")?;
+    initial
+        .checked_sub(total_diff)
+        .ok_or("Prediction overflows.")
 }
 
-fn predict_last(triangle: &[Vec<i32>]) -> i32 {
-    triangle.iter().map(|v| v.last().unwrap()).copied().sum()
+fn predict_last(triangle: &[Vec<i32>]) -> Result<i32, &'static str> {
+    triangle
+        .iter()
+        .map(|v| v.last().unwrap())
+        .try_fold(0, |total: i32, &x| total.checked_add(x))
+        .ok_or("Prediction overflows.")
 }
 
-fn difference_triangle(xs: &[i32]) -> Vec<Vec<i32>> {
+/// Rows of differences down to the first row of zeros, or an error if the
+/// rows run out first, which means the history has no polynomial fit.
+/// Every row is non-empty.
+fn difference_triangle(xs: &[i32]) -> Result<Vec<Vec<i32>>, &'static str> {
     let mut out: Vec<Vec<i32>> = vec![];
     let mut current = xs.to_vec();
     while !current.iter().all(|&x| x == 0) {
-        let next_diffs = differences(&current);
+        let next_diffs = differences(&current).ok_or("Difference overflows.")?;
         out.push(current);
         current = next_diffs;
     }
+    if current.is_empty() {
+        return Err("Differences run out before reaching zeros.");
+    }
     out.push(current);
-    out
+    Ok(out)
+}
+
+fn differences(xs: &[i32]) -> Option<Vec<i32>> {
+    xs.windows(2)
+        .map(|window| window[1].checked_sub(window[0]))
+        .collect()
+}
+
+/// Newton forward-difference coefficients for a history: the first value
+/// of each row of the difference triangle, computed with big integers so
+/// steep histories can't overflow.
+///
+/// Every row is kept, down to the single value at the bottom, so the
+/// coefficients describe the unique polynomial of degree less than the
+/// history's length that passes through every value.
+fn newton_coefficients(history: &[i32]) -> Vec<BigInt> {
+    let mut coefficients = Vec::with_capacity(history.len());
+    let mut current: Vec<BigInt> = history.iter().map(|&x| BigInt::from(x)).collect();
+    while let Some(first) = current.first() {
+        coefficients.push(first.clone());
+        current = current.windows(2).map(|w| &w[1] - &w[0]).collect();
+    }
+    coefficients
+}
+
+/// Evaluate the history's polynomial at any index, where 0 is the first
+/// value of the history. Negative indices extrapolate backwards, and
+/// indices past the end extrapolate forwards.
+fn extrapolate(history: &[i32], index: i64) -> BigInt {
+    Polynomial::interpolate(history).evaluate(index)
 }
 
-fn differences(xs: &[i32]) -> Vec<i32> {
-    xs.windows(2).map(|window| window[1] - window[0]).collect()
+/// A polynomial in Newton's forward-difference form:
+///  p(x) = Σ Δᵏy₀ · C(x, k)
+///
+/// Where C(x, k) = x(x - 1)…(x - k + 1) / k!, which is an integer for
+/// any integer x, so evaluating it stays exact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Polynomial {
+    /// Newton coefficients, Δᵏy₀, with trailing zeros removed.
+    newton: Vec<BigInt>,
+}
+
+impl Polynomial {
+    /// The unique polynomial of degree less than the history's length
+    /// that passes through every value.
+    fn interpolate(history: &[i32]) -> Self {
+        let mut newton = newton_coefficients(history);
+        while newton.last().is_some_and(Zero::is_zero) {
+            newton.pop();
+        }
+        Self { newton }
+    }
+
+    /// Fit a polynomial to the history, but only if the difference
+    /// triangle reaches a row of zeros within the history's length.
+    ///
+    /// Any n values can be matched by a polynomial of degree n - 1, so
+    /// that alone says nothing about whether the history was generated
+    /// by a polynomial. Reaching a row of zeros means at least one value
+    /// is predicted by the others.
+    fn fit(history: &[i32]) -> Result<Self, &'static str> {
+        if history.is_empty() {
+            return Err("History is empty.");
+        }
+        let polynomial = Self::interpolate(history);
+        if polynomial.newton.len() == history.len() {
+            return Err("History has no polynomial fit within its length.");
+        }
+        Ok(polynomial)
+    }
+
+    /// Degree of the polynomial, where the zero polynomial has degree 0.
+    fn degree(&self) -> usize {
+        self.newton.len().saturating_sub(1)
+    }
+
+    fn evaluate(&self, index: i64) -> BigInt {
+        let x = BigInt::from(index);
+        let mut binomial = BigInt::one();
+        let mut total = BigInt::zero();
+        for (k, coefficient) in self.newton.iter().enumerate() {
+            total += coefficient * &binomial;
+            // C(x, k + 1) = C(x, k) · (x - k) / (k + 1), which divides exactly.
+            binomial = binomial * (&x - k) / (k + 1);
+        }
+        total
+    }
+
+    /// Coefficients in the usual power basis, lowest power first, so that
+    ///  p(x) = c₀ + c₁x + c₂x² + …
+    ///
+    /// These are rational, as the binomials divide by k!.
+    fn coefficients(&self) -> Vec<BigRational> {
+        let mut coefficients = vec![BigRational::zero(); self.newton.len()];
+        // Power-basis coefficients of C(x, k), starting with C(x, 0) = 1.
+        let mut binomial = vec![BigRational::one()];
+        for (k, newton) in self.newton.iter().enumerate() {
+            let newton = BigRational::from(newton.clone());
+            for (c, b) in coefficients.iter_mut().zip(&binomial) {
+                *c += &newton * b;
+            }
+            // Multiply by (x - k) / (k + 1) to get C(x, k + 1).
+            let k = BigRational::from(BigInt::from(k));
+            let divisor = &k + BigRational::one();
+            let mut next = vec![BigRational::zero(); binomial.len() + 1];
+            for (power, b) in binomial.iter().enumerate() {
+                next[power + 1] += b / &divisor;
+                next[power] -= b * &k / &divisor;
+            }
+            binomial = next;
+        }
+        coefficients
+    }
+}
+
+/// Written in the power basis, highest power first, with fractional
+/// coefficients in brackets, e.g. (1/2)x^2 + (3/2)x + 1.
+impl Display for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut out = String::new();
+        for (power, c) in self.coefficients().iter().enumerate().rev() {
+            if c.is_zero() {
+                continue;
+            }
+            match (out.is_empty(), c.is_negative()) {
+                (true, true) => out.push('-'),
+                (true, false) => {}
+                (false, true) => out.push_str(" - "),
+                (false, false) => out.push_str(" + "),
+            }
+            let magnitude = c.abs();
+            if !magnitude.is_integer() {
+                write!(out, "({magnitude})")?;
+            } else if power == 0 || !magnitude.is_one() {
+                write!(out, "{magnitude}")?;
+            }
+            match power {
+                0 => {}
+                1 => out.push('x'),
+                _ => write!(out, "x^{power}")?,
+            }
+        }
+        if out.is_empty() {
+            out.push('0');
+        }
+        write!(f, "{out}")
+    }
+}
+
+/// A history that isn't generated by a polynomial of degree less than its
+/// length, so its predictions can't be trusted.
+#[derive(Debug, PartialEq, Eq)]
+struct InvalidHistory {
+    /// Line number in the input, starting from 1.
+    line: usize,
+    reason: &'static str,
+}
+
+impl Display for InvalidHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+fn validate(histories: &[Vec<i32>]) -> Vec<InvalidHistory> {
+    histories
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, history)| {
+            Polynomial::fit(history).err().map(|reason| InvalidHistory {
+                line: idx + 1,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// What to do with histories that fail validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnInvalid {
+    /// Leave them out of the sums, but list them in the result.
+    Skip,
+    /// Return every invalid history as an error.
+    Fail,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Predictions {
+    front: BigInt,
+    back: BigInt,
+    skipped: Vec<InvalidHistory>,
+}
+
+fn predict_sum_ends_checked(
+    histories: &[Vec<i32>],
+    on_invalid: OnInvalid,
+) -> Result<Predictions, Vec<InvalidHistory>> {
+    let invalid = validate(histories);
+    if on_invalid == OnInvalid::Fail && !invalid.is_empty() {
+        return Err(invalid);
+    }
+
+    let (front, back) = histories
+        .iter()
+        .flat_map(|history| predict_ends(history))
+        .fold((BigInt::zero(), BigInt::zero()), |(front, back), (f, b)| {
+            (front + f, back + b)
+        });
+
+    Ok(Predictions {
+        front,
+        back,
+        skipped: invalid,
+    })
 }
 
 fn parse_input(input: &str) -> Vec<Vec<i32>> {
@@ -57,7 +335,13 @@ fn parse_input(input: &str) -> Vec<Vec<i32>> {
 
 #[cfg(test)]
 mod test {
-    use super::{difference_triangle, differences, parse_input, predict_ends, predict_sum_ends};
+    use num::{BigInt, BigRational};
+
+    use super::{
+        difference_triangle, differences, extrapolate, fit_report, newton_coefficients,
+        parse_input, predict_ends, predict_ends_triangle, predict_sum_ends_checked, validate,
+        InvalidHistory, OnInvalid, Polynomial,
+    };
 
     const TEST_INPUT: &str = "\
         0 3 6 9 12 15\n\
@@ -80,7 +364,7 @@ mod test {
         let parsed = parse_input(TEST_INPUT);
         let xs = &parsed[0];
         let expected = vec![3; 5];
-        assert_eq!(differences(xs), expected);
+        assert_eq!(differences(xs), Some(expected));
     }
 
     #[test]
@@ -88,62 +372,272 @@ mod test {
         let parsed = parse_input(TEST_INPUT);
         let xs = &parsed[0];
         let expected = vec![xs.to_vec(), vec![3; 5], vec![0; 4]];
-        assert_eq!(difference_triangle(xs), expected);
+        assert_eq!(difference_triangle(xs), Ok(expected));
     }
 
     #[test]
     fn day9_predict_last() {
         let parsed = parse_input(TEST_INPUT);
         let xs = &parsed[0];
-        assert_eq!(predict_ends(xs).1, 18);
+        assert_eq!(predict_ends_triangle(xs).unwrap().1, 18);
     }
 
     #[test]
     fn day9_predict_last_2() {
         let parsed = parse_input(TEST_INPUT);
         let xs = &parsed[1];
-        assert_eq!(predict_ends(xs).1, 28);
+        assert_eq!(predict_ends_triangle(xs).unwrap().1, 28);
     }
 
     #[test]
     fn day9_predict_last_3() {
         let parsed = parse_input(TEST_INPUT);
         let xs = &parsed[2];
-        assert_eq!(predict_ends(xs).1, 68);
+        assert_eq!(predict_ends_triangle(xs).unwrap().1, 68);
     }
 
     #[test]
     fn day9_predict_first_1() {
         let parsed = parse_input(TEST_INPUT);
         let xs = &parsed[0];
-        assert_eq!(predict_ends(xs).0, -3);
+        assert_eq!(predict_ends_triangle(xs).unwrap().0, -3);
     }
 
     #[test]
     fn day9_predict_first_2() {
         let parsed = parse_input(TEST_INPUT);
         let xs = &parsed[1];
-        assert_eq!(predict_ends(xs).0, 0);
+        assert_eq!(predict_ends_triangle(xs).unwrap().0, 0);
     }
 
     #[test]
     fn day9_predict_first_3() {
         let parsed = parse_input(TEST_INPUT);
         let xs = &parsed[2];
-        assert_eq!(predict_ends(xs).0, 5);
+        assert_eq!(predict_ends_triangle(xs).unwrap().0, 5);
+    }
+
+    #[test]
+    fn day9_triangle_without_fit() {
+        // Each row is one shorter and none is all zeros, so they run out.
+        assert_eq!(
+            difference_triangle(&[1, 2, 4, 8, 16]),
+            Err("Differences run out before reaching zeros.")
+        );
+        assert_eq!(
+            predict_ends_triangle(&[]),
+            Err("Differences run out before reaching zeros.")
+        );
+        assert_eq!(differences(&[i32::MIN, i32::MAX]), None);
+        assert_eq!(
+            difference_triangle(&[i32::MIN, i32::MAX, i32::MIN]),
+            Err("Difference overflows.")
+        );
+    }
+
+    #[test]
+    fn day9_predict_ends() {
+        let predictions: Vec<(BigInt, BigInt)> = parse_input(TEST_INPUT)
+            .iter()
+            .map(|xs| predict_ends(xs).unwrap())
+            .collect();
+        let expected: Vec<(BigInt, BigInt)> = [(-3, 18), (0, 28), (5, 68)]
+            .map(|(front, back)| (BigInt::from(front), BigInt::from(back)))
+            .to_vec();
+        assert_eq!(predictions, expected);
+    }
+
+    #[test]
+    fn day9_predict_ends_without_fit() {
+        // The differences never vanish, so there's nothing to extrapolate.
+        assert_eq!(
+            predict_ends(&[1, 2, 4, 8, 16]),
+            Err("History has no polynomial fit within its length.")
+        );
+        assert_eq!(predict_ends(&[]), Err("History is empty."));
     }
 
     #[test]
     fn day9_both_test_input() {
-        let (front, back) = predict_sum_ends(parse_input(TEST_INPUT));
-        assert_eq!(back, 114, "Part one test input");
-        assert_eq!(front, 2, "Part two test input");
+        let predictions =
+            predict_sum_ends_checked(&parse_input(TEST_INPUT), OnInvalid::Fail).unwrap();
+        assert_eq!(predictions.back, BigInt::from(114), "Part one test input");
+        assert_eq!(predictions.front, BigInt::from(2), "Part two test input");
     }
 
     #[test]
     fn day9_both_real_input() {
-        let (front, back) = predict_sum_ends(parse_input(super::INPUT));
-        assert_eq!(back, 1782868781, "Part one real input");
-        assert_eq!(front, 1057, "Part two real input");
+        let predictions =
+            predict_sum_ends_checked(&parse_input(super::INPUT), OnInvalid::Fail).unwrap();
+        assert_eq!(
+            predictions.back,
+            BigInt::from(1782868781),
+            "Part one real input"
+        );
+        assert_eq!(predictions.front, BigInt::from(1057), "Part two real input");
+    }
+
+    #[test]
+    fn day9_newton_coefficients() {
+        let parsed = parse_input(TEST_INPUT);
+        let expected: Vec<BigInt> = [0, 3, 0, 0, 0, 0].map(BigInt::from).to_vec();
+        assert_eq!(newton_coefficients(&parsed[0]), expected);
+    }
+
+    #[test]
+    fn day9_exact_matches_triangle() {
+        for input in [TEST_INPUT, super::INPUT] {
+            for history in parse_input(input) {
+                let (front, back) = predict_ends_triangle(&history).unwrap();
+                let expected = (BigInt::from(front), BigInt::from(back));
+                assert_eq!(predict_ends(&history), Ok(expected), "{history:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn day9_extrapolate_far() {
+        let parsed = parse_input(TEST_INPUT);
+        assert_eq!(extrapolate(&parsed[0], 1_000_000), BigInt::from(3_000_000));
+        assert_eq!(
+            extrapolate(&parsed[0], -1_000_000),
+            BigInt::from(-3_000_000)
+        );
+        // Triangular numbers, offset by one.
+        assert_eq!(extrapolate(&parsed[1], 9), BigInt::from(55));
+        assert_eq!(extrapolate(&parsed[1], -3), BigInt::from(1));
+    }
+
+    #[test]
+    fn day9_extrapolate_beyond_i32() {
+        // 30x⁸ fits in an i32 up to x = 9, but not at x = 10.
+        let history: Vec<i32> = (0..10).map(|x: i32| 30 * x.pow(8)).collect();
+        assert_eq!(extrapolate(&history, 10), BigInt::from(3_000_000_000_i64));
+        assert_eq!(
+            predict_ends_triangle(&history),
+            Err("Prediction overflows.")
+        );
+        assert!(fit_report(&[history], None)
+            .ends_with("p(10) = 3000000000, difference triangle failed: Prediction overflows."));
+    }
+
+    #[test]
+    fn day9_polynomial_degree() {
+        let parsed = parse_input(TEST_INPUT);
+        let degrees: Vec<usize> = parsed
+            .iter()
+            .map(|h| Polynomial::fit(h).unwrap().degree())
+            .collect();
+        assert_eq!(degrees, vec![1, 2, 3]);
+        assert_eq!(Polynomial::fit(&[7, 7, 7]).unwrap().degree(), 0);
+        assert_eq!(Polynomial::fit(&[0, 0]).unwrap().degree(), 0);
+    }
+
+    #[test]
+    fn day9_polynomial_coefficients() {
+        let parsed = parse_input(TEST_INPUT);
+        let ratio = |n, d| BigRational::new(BigInt::from(n), BigInt::from(d));
+
+        // 3x
+        let coefficients = Polynomial::fit(&parsed[0]).unwrap().coefficients();
+        assert_eq!(coefficients, vec![ratio(0, 1), ratio(3, 1)]);
+
+        // (x + 1)(x + 2) / 2 = 1 + 3x/2 + x²/2
+        let coefficients = Polynomial::fit(&parsed[1]).unwrap().coefficients();
+        assert_eq!(coefficients, vec![ratio(1, 1), ratio(3, 2), ratio(1, 2)]);
+    }
+
+    #[test]
+    fn day9_polynomial_coefficients_evaluate_history() {
+        for history in parse_input(super::INPUT) {
+            let coefficients = Polynomial::fit(&history).unwrap().coefficients();
+            for (x, &y) in history.iter().enumerate() {
+                let x = BigRational::from(BigInt::from(x));
+                let value = coefficients
+                    .iter()
+                    .rev()
+                    .fold(BigRational::from(BigInt::from(0)), |acc, c| acc * &x + c);
+                assert_eq!(value, BigRational::from(BigInt::from(y)));
+            }
+        }
+    }
+
+    #[test]
+    fn day9_polynomial_no_fit() {
+        assert!(Polynomial::fit(&[]).is_err());
+        assert!(Polynomial::fit(&[1]).is_err());
+        assert!(Polynomial::fit(&[1, 2, 4, 8, 16]).is_err());
+        assert!(Polynomial::fit(&[1, 2, 4, 8, 15]).is_ok());
+    }
+
+    const INVALID_INPUT: &str = "\
+        0 3 6 9 12 15\n\
+        1 2 4 8 16\n\
+        10 13 16 21 30 45\n\
+        \n\
+        5";
+
+    #[test]
+    fn day9_validate() {
+        assert!(validate(&parse_input(TEST_INPUT)).is_empty());
+        assert!(validate(&parse_input(super::INPUT)).is_empty());
+
+        let invalid = validate(&parse_input(INVALID_INPUT));
+        let lines: Vec<usize> = invalid.iter().map(|i| i.line).collect();
+        assert_eq!(lines, vec![2, 4, 5]);
+        assert_eq!(
+            invalid[0].to_string(),
+            "line 2: History has no polynomial fit within its length."
+        );
+        assert_eq!(invalid[1].to_string(), "line 4: History is empty.");
+    }
+
+    #[test]
+    fn day9_checked_skip() {
+        let predictions =
+            predict_sum_ends_checked(&parse_input(INVALID_INPUT), OnInvalid::Skip).unwrap();
+        assert_eq!(predictions.back, BigInt::from(18 + 68));
+        assert_eq!(predictions.front, BigInt::from(-3 + 5));
+        assert_eq!(predictions.skipped.len(), 3);
+    }
+
+    #[test]
+    fn day9_checked_fail() {
+        let result = predict_sum_ends_checked(&parse_input(INVALID_INPUT), OnInvalid::Fail);
+        let lines: Vec<usize> = result.unwrap_err().iter().map(|i| i.line).collect();
+        assert_eq!(lines, vec![2, 4, 5]);
+
+        let predictions =
+            predict_sum_ends_checked(&parse_input(TEST_INPUT), OnInvalid::Fail).unwrap();
+        assert_eq!(predictions.back, BigInt::from(114));
+        assert_eq!(predictions.front, BigInt::from(2));
+        assert_eq!(predictions.skipped, Vec::<InvalidHistory>::new());
+    }
+
+    #[test]
+    fn day9_polynomial_display() {
+        let shown: Vec<String> = [&[0, 3, 6][..], &[1, 3, 6, 10], &[5, 3, 1, -1], &[0, 0]]
+            .iter()
+            .map(|h| Polynomial::fit(h).unwrap().to_string())
+            .collect();
+        assert_eq!(shown, vec!["3x", "(1/2)x^2 + (3/2)x + 1", "-2x + 5", "0"]);
+        assert_eq!(
+            Polynomial::fit(&[1, 0, 1, 4]).unwrap().to_string(),
+            "x^2 - 2x + 1"
+        );
+    }
+
+    #[test]
+    fn day9_fit_report() {
+        let expected = "\
+line 1: degree 1, p(x) = 3x, p(-1) = -3, p(6) = 18, p(1000000) = 3000000
+line 2: History has no polynomial fit within its length.
+line 3: degree 3, p(x) = (1/3)x^3 - x^2 + (11/3)x + 10, p(-1) = 5, p(6) = 68, p(1000000) = 333332333337000010
+line 4: History is empty.
+line 5: History has no polynomial fit within its length.";
+        assert_eq!(
+            fit_report(&parse_input(INVALID_INPUT), Some(1_000_000)),
+            expected
+        );
     }
 }
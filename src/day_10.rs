@@ -2,7 +2,6 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
-    iter,
     str::FromStr,
 };
 
@@ -20,7 +19,7 @@ const PLAIN_LOOP: &str = "\
 pub fn run() -> String {
     let mut m: Map = INPUT.parse().unwrap();
     m.fill_distances();
-    utils::first(m.max_distance())
+    utils::both(m.max_distance(), m.interior_points())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,6 +39,10 @@ impl Segment {
         matches!(self, Self::Start)
     }
 
+    fn is_pipe(&self) -> bool {
+        !matches!(self, Self::Ground | Self::Start)
+    }
+
     fn connects_south(&self) -> bool {
         matches!(self, Self::Vertical | Self::SouthToWest | Self::SouthToEast)
     }
@@ -121,7 +124,9 @@ struct Map {
 
 impl Map {
     fn new(map: HashMap<Position, Segment>, start: Position) -> Self {
-        let distances = map.keys().copied().zip(iter::repeat(0)).collect();
+        // Only visited tiles have a distance, so junk pipes and ground
+        // aren't confused with the start.
+        let distances = HashMap::from([(start, 0)]);
         Self {
             map,
             distances,
@@ -137,7 +142,7 @@ impl Map {
         self.map[&current]
             .neighbours(current)
             .into_iter()
-            .find(|pos| *pos != self.start && self.distances[pos] == 0)
+            .find(|pos| !self.distances.contains_key(pos))
     }
 
     fn fill_distances(&mut self) {
@@ -153,11 +158,237 @@ impl Map {
     }
 
     fn max_distance(&mut self) -> usize {
-        if self.distances.values().all(|&d| d == 0) {
+        if self.distances.len() == 1 {
             self.fill_distances();
         }
         self.distances.values().max().unwrap() / 2 + 1
     }
+
+    /// Positions of the loop, in walking order, beginning at the start.
+    fn loop_positions(&self) -> Vec<Position> {
+        let mut positions = vec![self.start];
+        let mut previous = self.start;
+        let mut current = self.map[&self.start].neighbours(self.start)[0];
+        while current != self.start {
+            positions.push(current);
+            let next = self.map[&current]
+                .neighbours(current)
+                .into_iter()
+                .find(|&pos| pos != previous)
+                .unwrap();
+            previous = current;
+            current = next;
+        }
+        positions
+    }
+
+    /// The loop through the start position.
+    fn main_loop(&self) -> PipeLoop {
+        PipeLoop {
+            positions: self.loop_positions(),
+        }
+    }
+
+    fn vertices(&self) -> Vec<Position> {
+        self.main_loop().vertices()
+    }
+
+    fn shoelace_area(&self) -> isize {
+        self.main_loop().shoelace_area()
+    }
+
+    fn interior_points(&self) -> usize {
+        self.main_loop().interior_points()
+    }
+
+    /// Whether a tile is part of the main loop, a pipe that isn't, or ground.
+    ///
+    /// The loop's tiles are exactly those with a distance, so the distances
+    /// are filled in first if they haven't been.
+    fn tile_kind(&mut self, pos: Position) -> Option<TileKind> {
+        if self.distances.len() == 1 {
+            self.fill_distances();
+        }
+        let segment = self.at(pos)?;
+        Some(if self.distances.contains_key(&pos) {
+            TileKind::Loop
+        } else if segment.is_pipe() {
+            TileKind::Junk
+        } else {
+            TileKind::Ground
+        })
+    }
+
+    /// Neighbours of a pipe, but only if both of those neighbours are
+    /// pipes that connect back to it.
+    fn connected_neighbours(&self, pos: Position) -> Option<[Position; 2]> {
+        let segment = self.at(pos).filter(Segment::is_pipe)?;
+        let neighbours = segment.neighbours(pos);
+        neighbours
+            .iter()
+            .all(|&n| {
+                self.at(n)
+                    .filter(Segment::is_pipe)
+                    .is_some_and(|s| s.neighbours(n).contains(&pos))
+            })
+            .then_some(neighbours)
+    }
+
+    /// Follow the pipes from a position until they either return to it,
+    /// giving a closed loop, or the chain is broken. On failure, the error
+    /// holds the positions that were visited before the chain broke.
+    fn trace_loop(&self, from: Position) -> Result<PipeLoop, Vec<Position>> {
+        let mut positions = vec![from];
+        let Some([mut current, _]) = self.connected_neighbours(from) else {
+            return Err(positions);
+        };
+        let mut previous = from;
+        while current != from {
+            positions.push(current);
+            let Some(neighbours) = self.connected_neighbours(current) else {
+                return Err(positions);
+            };
+            let next = neighbours.into_iter().find(|&n| n != previous).unwrap();
+            previous = current;
+            current = next;
+        }
+        Ok(PipeLoop { positions })
+    }
+
+    /// Every closed loop of pipes in the map, including the main loop.
+    fn find_loops(&self) -> Vec<PipeLoop> {
+        let mut seen: HashSet<Position> = HashSet::new();
+        let mut loops = Vec::new();
+        let mut positions: Vec<Position> = self.map.keys().copied().collect();
+        // Sort so the loops are found in reading order.
+        positions.sort_by_key(|&(x, y)| (y, x));
+        for pos in positions {
+            if seen.contains(&pos) || !self.map[&pos].is_pipe() {
+                continue;
+            }
+            match self.trace_loop(pos) {
+                Ok(pipe_loop) => {
+                    seen.extend(pipe_loop.positions.iter().copied());
+                    loops.push(pipe_loop);
+                }
+                Err(visited) => seen.extend(visited),
+            }
+        }
+        loops
+    }
+
+    /// Number of tiles enclosed by the loop, by flood-filling from outside.
+    ///
+    /// Each tile is expanded to a 3x3 block so that the fill can squeeze
+    /// between adjacent pipes that don't connect.
+    fn flood_fill_interior(&self) -> usize {
+        let loop_positions: HashSet<Position> = self.loop_positions().into_iter().collect();
+        let width = self.map.keys().map(|p| p.0).max().unwrap() + 1;
+        let height = self.map.keys().map(|p| p.1).max().unwrap() + 1;
+
+        let mut walls: HashSet<Position> = HashSet::new();
+        for &(x, y) in &loop_positions {
+            let centre = (x * 3 + 1, y * 3 + 1);
+            walls.insert(centre);
+            for (nx, ny) in self.map[&(x, y)].neighbours(centre) {
+                // Neighbours are one step away, which in the expanded grid
+                // is the edge of the block in that direction.
+                walls.insert((nx, ny));
+            }
+        }
+
+        // Pad by one expanded cell on each side so the fill can get all
+        // the way around the loop.
+        let in_bounds =
+            |(x, y): Position| (-1..=width * 3).contains(&x) && (-1..=height * 3).contains(&y);
+        let mut outside: HashSet<Position> = HashSet::new();
+        let mut queue = vec![(-1, -1)];
+        while let Some(pos @ (x, y)) = queue.pop() {
+            if !in_bounds(pos) || walls.contains(&pos) || !outside.insert(pos) {
+                continue;
+            }
+            queue.extend([(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)]);
+        }
+
+        self.map
+            .keys()
+            .filter(|pos| !loop_positions.contains(pos))
+            .filter(|&&(x, y)| !outside.contains(&(x * 3 + 1, y * 3 + 1)))
+            .count()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TileKind {
+    Loop,
+    Junk,
+    Ground,
+}
+
+/// A closed loop of connected pipes, in walking order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PipeLoop {
+    positions: Vec<Position>,
+}
+
+impl PipeLoop {
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// The corners of the loop, which are the vertices of the polygon
+    /// it describes. Straight sections are skipped as they lie on the
+    /// edges between vertices.
+    fn vertices(&self) -> Vec<Position> {
+        let n = self.positions.len();
+        (0..n)
+            .filter(|&idx| {
+                let before = self.positions[(idx + n - 1) % n];
+                let after = self.positions[(idx + 1) % n];
+                before.0 != after.0 && before.1 != after.1
+            })
+            .map(|idx| self.positions[idx])
+            .collect()
+    }
+
+    /// Signed area of the loop polygon, using the shoelace formula.
+    ///
+    /// The sign depends on the direction the loop is walked: positive
+    /// is clockwise on screen, as y increases downwards.
+    ///
+    /// The loop only has horizontal and vertical edges between integer
+    /// vertices, so the area is always a whole number.
+    fn shoelace_area(&self) -> isize {
+        let vertices = self.vertices();
+        let twice_area: isize = vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(&(x1, y1), &(x2, y2))| x1 * y2 - x2 * y1)
+            .sum();
+        twice_area / 2
+    }
+
+    /// Number of tiles enclosed by the loop, using Pick's theorem:
+    ///  A = i + b/2 - 1
+    ///
+    /// Where A is the polygon area, i the number of interior points,
+    /// and b the number of boundary points (the length of the loop).
+    fn interior_points(&self) -> usize {
+        let area = self.shoelace_area().unsigned_abs();
+        area + 1 - self.len() / 2
+    }
+}
+
+impl Display for PipeLoop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (x, y) = self.positions[0];
+        write!(
+            f,
+            "Loop at ({x}, {y}): length {}, enclosing {} tiles",
+            self.len(),
+            self.interior_points()
+        )
+    }
 }
 
 impl Display for Map {
@@ -170,11 +401,9 @@ impl Display for Map {
                     if (x, y) == self.start {
                         "S".to_owned()
                     } else {
-                        let d = self.distances[&(x, y)];
-                        if d == 0 {
-                            " ".to_owned()
-                        } else {
-                            d.to_string()
+                        match self.distances.get(&(x, y)) {
+                            Some(d) => d.to_string(),
+                            None => " ".to_owned(),
                         }
                     }
                 };
@@ -227,7 +456,7 @@ impl FromStr for Map {
 
 #[cfg(test)]
 mod test {
-    use super::{Map, Segment};
+    use super::{Map, Position, Segment, TileKind};
 
     const PLAIN_LOOP: &str = "\
         .....\n\
@@ -241,6 +470,32 @@ mod test {
         SJ.L7\n\
         |F--J\n\
         LJ...";
+    const ENCLOSED_LOOP: &str = "\
+        ...........\n\
+        .S-------7.\n\
+        .|F-----7|.\n\
+        .||.....||.\n\
+        .||.....||.\n\
+        .|L-7.F-J|.\n\
+        .|..|.|..|.\n\
+        .L--J.L--J.\n\
+        ...........";
+    const SQUEEZED_LOOP: &str = "\
+        ..........\n\
+        .S------7.\n\
+        .|F----7|.\n\
+        .||....||.\n\
+        .||....||.\n\
+        .|L-7F-J|.\n\
+        .|..||..|.\n\
+        .L--JL--J.\n\
+        ..........";
+    const MULTIPLE_LOOPS: &str = "\
+        .....F-7\n\
+        .S-7.|.|\n\
+        .|.|.L-J\n\
+        .L-J.F7.\n\
+        .....-|.";
 
     #[test]
     fn day10_test_parse() {
@@ -276,4 +531,108 @@ mod test {
         assert!(Segment::SouthToEast.connects_south());
         assert!(Segment::SouthToEast.connects_east());
     }
+
+    #[test]
+    fn day10_test_vertices() {
+        let map: Map = PLAIN_LOOP.parse().unwrap();
+        let mut vertices = map.vertices();
+        vertices.sort();
+        assert_eq!(vertices, vec![(1, 1), (1, 3), (3, 1), (3, 3)]);
+    }
+
+    #[test]
+    fn day10_test_shoelace_area() {
+        let map: Map = PLAIN_LOOP.parse().unwrap();
+        assert_eq!(map.shoelace_area().abs(), 4);
+        let map: Map = COMPLEX_LOOP.parse().unwrap();
+        assert_eq!(map.shoelace_area().abs(), 8);
+    }
+
+    #[test]
+    fn day10_test_interior_points() {
+        let map: Map = PLAIN_LOOP.parse().unwrap();
+        assert_eq!(map.interior_points(), 1);
+        let map: Map = ENCLOSED_LOOP.parse().unwrap();
+        assert_eq!(map.interior_points(), 4);
+        let map: Map = SQUEEZED_LOOP.parse().unwrap();
+        assert_eq!(map.interior_points(), 4);
+    }
+
+    #[test]
+    fn day10_pick_matches_flood_fill() {
+        for input in [
+            PLAIN_LOOP,
+            COMPLEX_LOOP,
+            ENCLOSED_LOOP,
+            SQUEEZED_LOOP,
+            super::INPUT,
+        ] {
+            let map: Map = input.parse().unwrap();
+            assert_eq!(map.interior_points(), map.flood_fill_interior());
+        }
+    }
+
+    #[test]
+    fn day10_real_interior_points() {
+        let map: Map = super::INPUT.parse().unwrap();
+        assert_eq!(map.interior_points(), 273);
+    }
+
+    #[test]
+    fn day10_distances_only_cover_the_loop() {
+        let mut map: Map = MULTIPLE_LOOPS.parse().unwrap();
+        assert_eq!(map.max_distance(), 4);
+        assert_eq!(map.distances.len(), 8);
+        assert_eq!(map.distances[&map.start], 0);
+        assert!(!map.distances.contains_key(&(5, 0)));
+    }
+
+    #[test]
+    fn day10_tile_kinds() {
+        let mut map: Map = MULTIPLE_LOOPS.parse().unwrap();
+        assert_eq!(map.tile_kind((1, 1)), Some(TileKind::Loop));
+        assert_eq!(map.tile_kind((3, 3)), Some(TileKind::Loop));
+        assert_eq!(map.tile_kind((5, 0)), Some(TileKind::Junk));
+        assert_eq!(map.tile_kind((6, 4)), Some(TileKind::Junk));
+        assert_eq!(map.tile_kind((0, 0)), Some(TileKind::Ground));
+        assert_eq!(map.tile_kind((8, 0)), None);
+    }
+
+    #[test]
+    fn day10_real_tile_kinds() {
+        let mut map: Map = super::INPUT.parse().unwrap();
+        let positions: Vec<Position> = map.map.keys().copied().collect();
+        let loop_tiles = positions
+            .into_iter()
+            .filter(|&pos| map.tile_kind(pos) == Some(TileKind::Loop))
+            .count();
+        assert_eq!(loop_tiles, map.main_loop().len());
+    }
+
+    #[test]
+    fn day10_find_loops() {
+        let map: Map = MULTIPLE_LOOPS.parse().unwrap();
+        let loops = map.find_loops();
+        assert_eq!(loops.len(), 2);
+        for pipe_loop in &loops {
+            assert_eq!(pipe_loop.len(), 8);
+            assert_eq!(pipe_loop.interior_points(), 1);
+        }
+        assert_eq!(loops[0].positions[0], (5, 0));
+        assert!(loops[1].positions.contains(&map.start));
+        assert_eq!(
+            loops[0].to_string(),
+            "Loop at (5, 0): length 8, enclosing 1 tiles"
+        );
+    }
+
+    #[test]
+    fn day10_find_loops_includes_main_loop() {
+        let map: Map = super::INPUT.parse().unwrap();
+        let main_loop = map.main_loop();
+        let loops = map.find_loops();
+        assert!(loops
+            .iter()
+            .any(|l| l.len() == main_loop.len() && l.positions.contains(&map.start)));
+    }
 }
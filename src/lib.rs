@@ -14,9 +14,18 @@ pub use day_01::run as day01;
 pub use day_02::run as day02;
 pub use day_03::run as day03;
 pub use day_04::run as day04;
+pub use day_04::trace as day04_trace;
+pub use day_05::check as day05_check;
+pub use day_05::explain as day05_explain;
 pub use day_05::run as day05;
+pub use day_05::staged as day05_staged;
 pub use day_06::run as day06;
+pub use day_06::table as day06_table;
+pub use day_07::report as day07_report;
 pub use day_07::run as day07;
+pub use day_08::dot as day08_dot;
 pub use day_08::run as day08;
+pub use day_08::walk as day08_walk;
+pub use day_09::fit as day09_fit;
 pub use day_09::run as day09;
 pub use day_10::run as day10;
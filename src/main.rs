@@ -1,10 +1,53 @@
-use std::env::args;
+use std::{env::args, process};
 
 use aoc_2023::*;
 
 fn main() {
     let day: Option<usize> = args().nth(1).and_then(|s| s.parse().ok());
 
+    if let Some(subcommand) = args().nth(2) {
+        match (day, subcommand.as_str()) {
+            (Some(4), "trace") => println!("{}", day04_trace()),
+            (Some(5), "check") => println!("{}", day05_check()),
+            (Some(5), "explain") => println!("{}", day05_explain()),
+            (Some(5), "staged") => println!("{}", day05_staged()),
+            (Some(6), "table") => println!("{}", day06_table()),
+            (Some(7), "report") => {
+                let args: Vec<String> = args().collect();
+                let without: Vec<String> = args
+                    .windows(2)
+                    .filter(|w| w[0] == "--without")
+                    .map(|w| w[1].clone())
+                    .collect();
+                let jokers = args.iter().any(|a| a == "--jokers");
+                println!("{}", day07_report(jokers, &without))
+            }
+            (Some(8), "dot") => println!("{}", day08_dot(args().any(|a| a == "--paths"))),
+            (Some(8), "walk") => match args().nth(3).and_then(|s| s.parse().ok()) {
+                Some(steps) => println!("{}", day08_walk(steps)),
+                None => {
+                    eprintln!("Usage: 8 walk <steps>");
+                    process::exit(1);
+                }
+            },
+            (Some(9), "fit") => match args().skip_while(|a| a != "--at").nth(1) {
+                None => println!("{}", day09_fit(None)),
+                Some(at) => match at.parse() {
+                    Ok(at) => println!("{}", day09_fit(Some(at))),
+                    Err(_) => {
+                        eprintln!("Usage: 9 fit [--at <index>]");
+                        process::exit(1);
+                    }
+                },
+            },
+            _ => {
+                eprintln!("Unknown subcommand: {}", subcommand);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
     let fns = [
         day01, day02, day03, day04, day05, day06, day07, day08, day09, day10,
     ];
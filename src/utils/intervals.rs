@@ -0,0 +1,192 @@
+//! Sets of values stored as sorted ranges, for when there are far too
+//! many values to hold one at a time.
+//!
+//! The ranges in a set never overlap or touch, so every set has exactly
+//! one representation and equal sets compare equal.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self { ranges: Vec::new() }
+    }
+}
+
+impl<T: Ord + Copy> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The ranges in the set, sorted, with gaps between each.
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The lowest value in the set.
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let index = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges.get(index).is_some_and(|r| r.contains(&value))
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        *self = self.union(&Self::from(range));
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.ranges.iter().chain(&other.ranges).cloned().collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let start = a.start.max(b.start);
+            let end = a.end.min(b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+            // Whichever finishes first can't meet anything further on.
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    /// Values in this set but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            // Skip what finished before this range began.
+            while other.ranges.get(j).is_some_and(|b| b.end <= start) {
+                j += 1;
+            }
+            let mut k = j;
+            while let Some(b) = other.ranges.get(k).filter(|b| b.start < range.end) {
+                if start < b.start {
+                    ranges.push(start..b.start);
+                }
+                start = start.max(b.end);
+                k += 1;
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        Self { ranges }
+    }
+}
+
+impl<T: Ord + Copy> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        std::iter::once(range).collect()
+    }
+}
+
+/// Collect any ranges, in any order, coalescing those that overlap or
+/// touch and dropping those that are empty.
+impl<T: Ord + Copy> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut sorted: Vec<Range<T>> = iter.into_iter().filter(|r| r.start < r.end).collect();
+        sorted.sort_by_key(|r| r.start);
+
+        let mut ranges: Vec<Range<T>> = Vec::with_capacity(sorted.len());
+        for range in sorted {
+            match ranges.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => ranges.push(range),
+            }
+        }
+        Self { ranges }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::IntervalSet;
+    use crate::utils::rng::Lcg;
+
+    fn set(ranges: &[(u32, u32)]) -> IntervalSet<u32> {
+        ranges.iter().map(|&(a, b)| a..b).collect()
+    }
+
+    #[test]
+    fn coalescing() {
+        let s = set(&[(10, 20), (0, 5), (5, 8), (15, 25), (30, 30), (40, 41)]);
+        assert_eq!(s.ranges(), &[0..8, 10..25, 40..41]);
+        assert_eq!(s.min(), Some(0));
+        assert_eq!(set(&[(3, 3)]), IntervalSet::new());
+        assert!(set(&[]).is_empty());
+    }
+
+    #[test]
+    fn membership() {
+        let s = set(&[(0, 5), (10, 20)]);
+        let inside: Vec<u32> = (0..25).filter(|&v| s.contains(v)).collect();
+        let expected: Vec<u32> = (0..5).chain(10..20).collect();
+        assert_eq!(inside, expected);
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[(0, 10), (20, 30), (40, 50)]);
+        let b = set(&[(5, 25), (28, 42), (60, 70)]);
+        assert_eq!(a.union(&b), set(&[(0, 50), (60, 70)]));
+        assert_eq!(
+            a.intersection(&b),
+            set(&[(5, 10), (20, 25), (28, 30), (40, 42)])
+        );
+        assert_eq!(a.difference(&b), set(&[(0, 5), (25, 28), (42, 50)]));
+        assert_eq!(b.difference(&a), set(&[(10, 20), (30, 40), (60, 70)]));
+        assert_eq!(a.difference(&a), IntervalSet::new());
+        assert_eq!(a.intersection(&IntervalSet::new()), IntervalSet::new());
+
+        let mut c = a.clone();
+        c.insert(10..20);
+        assert_eq!(c, set(&[(0, 30), (40, 50)]));
+    }
+
+    #[test]
+    fn operations_agree_with_values() {
+        // Small pseudo-random sets checked value by value.
+        let mut rng = Lcg(12345);
+        for _ in 0..200 {
+            let mut random_set = || -> IntervalSet<u32> {
+                (0..4)
+                    .map(|_| {
+                        let start = rng.below(64) as u32;
+                        start..start + rng.below(8) as u32
+                    })
+                    .collect()
+            };
+            let (a, b) = (random_set(), random_set());
+            let (union, both, only_a) = (a.union(&b), a.intersection(&b), a.difference(&b));
+            for v in 0..80 {
+                let (in_a, in_b) = (a.contains(v), b.contains(v));
+                assert_eq!(union.contains(v), in_a || in_b);
+                assert_eq!(both.contains(v), in_a && in_b);
+                assert_eq!(only_a.contains(v), in_a && !in_b);
+            }
+            for s in [&union, &both, &only_a] {
+                assert!(s.ranges().windows(2).all(|w| w[0].end < w[1].start));
+            }
+        }
+    }
+}
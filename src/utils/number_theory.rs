@@ -0,0 +1,151 @@
+//! Integer helpers for puzzles that come down to cycles and congruences.
+//!
+//! Everything here is iterative and checked, so empty input, overflow and
+//! unsolvable systems come back as `None` or an error rather than a panic.
+
+pub fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// GCD of every number in the slice, or `None` if it's empty.
+pub fn gcd_of(xs: &[usize]) -> Option<usize> {
+    xs.iter().copied().reduce(gcd)
+}
+
+/// LCM of every number in the slice, or `None` if it's empty or the
+/// result doesn't fit in a usize.
+pub fn lcm(xs: &[usize]) -> Option<usize> {
+    let (&first, rest) = xs.split_first()?;
+    rest.iter().try_fold(first, |a, &b| {
+        if a == 0 || b == 0 {
+            return Some(0);
+        }
+        // Divide before multiplying so only a true overflow fails.
+        (a / gcd(a, b)).checked_mul(b)
+    })
+}
+
+/// Returns (g, x, y) such that ax + by = g = gcd(a, b).
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// The inverse of `a` modulo `m`, in the range 0..m, or `None` if `a` and
+/// `m` aren't coprime.
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    if m <= 0 {
+        return None;
+    }
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// Solve a system of congruences x ≡ r (mod m), given as (r, m) pairs,
+/// where the moduli need not be coprime.
+///
+/// Returns the solution as (residue, modulus), where the modulus is the
+/// LCM of the inputs. An empty system is solved by anything, (0, 1).
+pub fn crt(congruences: &[(usize, usize)]) -> Result<(usize, usize), &'static str> {
+    congruences.iter().try_fold((0, 1), |(r1, m1), &(r2, m2)| {
+        if m2 == 0 {
+            return Err("Modulus is zero.");
+        }
+        let r2 = r2 % m2;
+        let g = gcd(m1, m2);
+        let (low, high) = (r1.min(r2), r1.max(r2));
+        if (high - low) % g != 0 {
+            return Err("Congruences are inconsistent.");
+        }
+        let modulus = lcm(&[m1, m2]).ok_or("Combined modulus overflowed.")?;
+
+        // Solve r1 + m1·k ≡ r2 (mod m2) for k, working modulo m2 / g.
+        let step = (m2 / g) as i128;
+        let inverse = mod_inverse((m1 / g) as i128, step).unwrap() as u128;
+        let diff = ((r2 as i128 - r1 as i128) / g as i128).rem_euclid(step) as u128;
+        let k = diff * inverse % step as u128;
+        // k < m2 / g, so r1 + m1·k < lcm, which is known to fit.
+        let residue = r1 as u128 + m1 as u128 * k;
+        Ok((residue as usize, modulus))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crt, extended_gcd, gcd, gcd_of, lcm, mod_inverse};
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(0, 5), 5);
+        assert_eq!(gcd_of(&[12, 18, 27]), Some(3));
+        assert_eq!(gcd_of(&[]), None);
+        assert_eq!(lcm(&[1, 2, 3, 4, 5]), Some(60));
+        assert_eq!(lcm(&[7]), Some(7));
+        assert_eq!(lcm(&[4, 0]), Some(0));
+        assert_eq!(lcm(&[]), None);
+    }
+
+    #[test]
+    fn lcm_overflow() {
+        // These share a factor, so a * b would overflow but the LCM fits.
+        let big = usize::MAX / 3;
+        assert_eq!(lcm(&[big, big]), Some(big));
+        assert_eq!(lcm(&[usize::MAX, usize::MAX - 1]), None);
+    }
+
+    #[test]
+    fn extended_euclid() {
+        let (g, x, y) = extended_gcd(240, 46);
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+        let (g, x, y) = extended_gcd(-15, 10);
+        assert_eq!(g, 5);
+        assert_eq!(-15 * x + 10 * y, 5);
+    }
+
+    #[test]
+    fn modular_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(mod_inverse(3, 0), None);
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Ok((23, 105)));
+        assert_eq!(crt(&[(3, 5), (4, 7)]), Ok((18, 35)));
+        // Non-coprime moduli.
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Ok((10, 12)));
+        assert_eq!(crt(&[(0, 2), (1, 4)]), Err("Congruences are inconsistent."));
+        assert_eq!(crt(&[(0, 0)]), Err("Modulus is zero."));
+        assert_eq!(crt(&[]), Ok((0, 1)));
+    }
+
+    #[test]
+    fn chinese_remainder_overflow() {
+        let (a, b) = (usize::MAX, usize::MAX - 1);
+        assert_eq!(crt(&[(0, a), (0, b)]), Err("Combined modulus overflowed."));
+        // Large but coprime moduli whose product still fits.
+        let (m1, m2) = (4_294_967_291, 4_294_967_279);
+        let (residue, modulus) = crt(&[(5, m1), (7, m2)]).unwrap();
+        assert_eq!(modulus, m1 * m2);
+        assert_eq!((residue % m1, residue % m2), (5, 7));
+    }
+}